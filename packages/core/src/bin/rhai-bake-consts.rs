@@ -0,0 +1,43 @@
+//! Bake the consts of a cortex bundle offline (see `zip_module_resolver::bake_consts`).
+//!
+//! Consts are evaluated by an engine built as the contract's is (this crate's rhai
+//! features and enabled packages), so build with the contract's features.
+//!
+//! Usage: rhai-bake-consts <input bundle> <output file>
+
+use std::env;
+use std::fs;
+use std::process;
+
+use teggle_omnibus_core::{new_compile_engine, PackageKind};
+use zip_module_resolver::bake_consts;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <input bundle> <output file>", args[0]);
+        process::exit(2);
+    }
+
+    let bundle = fs::read(&args[1]).unwrap_or_else(|err| {
+        eprintln!("failed to read '{}': {}", args[1], err);
+        process::exit(1);
+    });
+
+    let engine = new_compile_engine(&PackageKind::enabled()).unwrap_or_else(|err| {
+        eprintln!("failed to build engine: {}", err);
+        process::exit(1);
+    });
+
+    let output = bake_consts(bundle, &engine).unwrap_or_else(|err| {
+        eprintln!("failed to bake consts of '{}': {}", args[1], err);
+        process::exit(1);
+    });
+
+    fs::write(&args[2], &output).unwrap_or_else(|err| {
+        eprintln!("failed to write '{}': {}", args[2], err);
+        process::exit(1);
+    });
+
+    println!("wrote {} ({} bytes)", args[2], output.len());
+}
@@ -0,0 +1,233 @@
+use cosmwasm_std::{Api, CanonicalAddr, HumanAddr, ReadonlyStorage, StdError, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket, singleton, singleton_read};
+use rhai::Map;
+
+use crate::cortex::config::CortexConfig;
+use crate::cortex::registry::load_bundle_config;
+use crate::rhai::json::from_json_str;
+
+pub const NS_CORTEX_ADMIN: &'static [u8] = b"cortex_admin";
+pub const NS_CORTEX_ROLES: &'static [u8] = b"cortex_roles";
+
+/// The contract admin approves the permissions granted to cortexes.
+pub const KEY_CONTRACT_ADMIN: &'static [u8] = b"contract_admin";
+
+const KEY_ADMIN: &'static [u8] = b"admin";
+const KEY_PAUSED: &'static [u8] = b"paused";
+const KEY_CONFIG_OVERRIDES: &'static [u8] = b"config_overrides";
+
+/// Built-in operations which may only be performed by the cortex admin.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminOp {
+    TransferAdmin { new_admin: HumanAddr },
+    Pause,
+    Unpause,
+    /// A json object of config keys to override (see `CortexConfig`).
+    SetConfigOverrides { overrides: String },
+    GrantRole { role: String, address: HumanAddr },
+    RevokeRole { role: String, address: HumanAddr },
+}
+
+pub fn apply_admin_op<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    name: &str,
+    sender: &HumanAddr,
+    op: AdminOp,
+) -> StdResult<()> {
+    ensure_admin(storage, api, name, sender)?;
+
+    match op {
+        AdminOp::TransferAdmin { new_admin } => {
+            store_admin(storage, name, &api.canonical_address(&new_admin)?)
+        }
+        AdminOp::Pause => set_paused(storage, name, true),
+        AdminOp::Unpause => set_paused(storage, name, false),
+        AdminOp::SetConfigOverrides { overrides } => {
+            let map = match from_json_str(&overrides).map_err(StdError::generic_err)?.try_cast::<Map>() {
+                Some(map) => map,
+                None => return Err(StdError::generic_err("config overrides must be a json object")),
+            };
+            let cfg = CortexConfig::new(load_bundle_config(storage, name)?);
+            ensure_overridable(&cfg, &map, "")?;
+
+            admin_bucket::<S, String>(storage, name).save(KEY_CONFIG_OVERRIDES, &overrides)
+        }
+        AdminOp::GrantRole { role, address } => {
+            set_role(storage, name, &role, &api.canonical_address(&address)?, true)
+        }
+        AdminOp::RevokeRole { role, address } => {
+            set_role(storage, name, &role, &api.canonical_address(&address)?, false)
+        }
+    }
+}
+
+/// Reject overrides of read-only keys (see `CortexConfig::is_readonly`), nested maps are
+/// checked key by key.
+fn ensure_overridable(cfg: &CortexConfig, overrides: &Map, prefix: &str) -> StdResult<()> {
+    for (key, value) in overrides.iter() {
+        let path = if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") };
+
+        match value.read_lock::<Map>() {
+            Some(nested) if !nested.is_empty() => ensure_overridable(cfg, &nested, &path)?,
+            _ => {
+                if cfg.is_readonly(&path) {
+                    return Err(StdError::generic_err(format!("config key '{path}' is read-only")));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the deployer as admin of a new cortex, or ensures they are the admin
+/// if the cortex is being upgraded.
+pub fn authorize_deploy<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    name: &str,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    let sender = api.canonical_address(sender)?;
+
+    return match load_admin(storage, name)? {
+        None => store_admin(storage, name, &sender),
+        Some(admin) if admin == sender => Ok(()),
+        Some(_) => Err(StdError::unauthorized()),
+    };
+}
+
+/// Record the contract admin, it may only be set once (see `transfer_contract_admin`).
+pub fn init_contract_admin<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    admin: &HumanAddr,
+) -> StdResult<()> {
+    if load_contract_admin(storage)?.is_some() {
+        return Err(StdError::generic_err("contract admin is already set"));
+    }
+
+    singleton(storage, KEY_CONTRACT_ADMIN).save(&api.canonical_address(admin)?)
+}
+
+pub fn transfer_contract_admin<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    sender: &HumanAddr,
+    new_admin: &HumanAddr,
+) -> StdResult<()> {
+    ensure_contract_admin(storage, api, sender)?;
+
+    singleton(storage, KEY_CONTRACT_ADMIN).save(&api.canonical_address(new_admin)?)
+}
+
+pub fn load_contract_admin<S: ReadonlyStorage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    singleton_read(storage, KEY_CONTRACT_ADMIN).may_load()
+}
+
+pub fn ensure_contract_admin<S: ReadonlyStorage, A: Api>(
+    storage: &S,
+    api: &A,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    let sender = api.canonical_address(sender)?;
+    if load_contract_admin(storage)? != Some(sender) {
+        return Err(StdError::unauthorized());
+    }
+
+    Ok(())
+}
+
+pub fn ensure_admin<S: ReadonlyStorage, A: Api>(
+    storage: &S,
+    api: &A,
+    name: &str,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    if !is_admin(storage, api, name, sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    Ok(())
+}
+
+pub fn load_admin<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Option<CanonicalAddr>> {
+    admin_bucket_read(storage, name).may_load(KEY_ADMIN)
+}
+
+pub fn store_admin<S: Storage>(storage: &mut S, name: &str, admin: &CanonicalAddr) -> StdResult<()> {
+    admin_bucket(storage, name).save(KEY_ADMIN, admin)
+}
+
+pub fn is_admin<S: ReadonlyStorage, A: Api>(
+    storage: &S,
+    api: &A,
+    name: &str,
+    addr: &HumanAddr,
+) -> StdResult<bool> {
+    let addr = api.canonical_address(addr)?;
+
+    Ok(load_admin(storage, name)?.map_or(false, |admin| admin == addr))
+}
+
+pub fn is_paused<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<bool> {
+    Ok(admin_bucket_read::<S, bool>(storage, name).may_load(KEY_PAUSED)?
+        .unwrap_or(false))
+}
+
+pub fn set_paused<S: Storage>(storage: &mut S, name: &str, paused: bool) -> StdResult<()> {
+    admin_bucket::<S, bool>(storage, name).save(KEY_PAUSED, &paused)
+}
+
+/// The config overrides set by the admin (layered over the bundle config).
+pub fn load_config_overrides<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Option<Map>> {
+    let json: Option<String> = admin_bucket_read(storage, name).may_load(KEY_CONFIG_OVERRIDES)?;
+
+    return match json {
+        None => Ok(None),
+        Some(json) => {
+            let overrides = from_json_str(&json).map_err(StdError::generic_err)?;
+            Ok(overrides.try_cast::<Map>())
+        }
+    };
+}
+
+pub fn has_role<S: ReadonlyStorage>(
+    storage: &S,
+    name: &str,
+    role: &str,
+    addr: &CanonicalAddr,
+) -> StdResult<bool> {
+    Ok(ReadonlyBucket::<S, bool>::multilevel(&[NS_CORTEX_ROLES, name.as_bytes(), role.as_bytes()], storage)
+        .may_load(addr.as_slice())?
+        .unwrap_or(false))
+}
+
+pub fn set_role<S: Storage>(
+    storage: &mut S,
+    name: &str,
+    role: &str,
+    addr: &CanonicalAddr,
+    granted: bool,
+) -> StdResult<()> {
+    let mut roles = Bucket::<S, bool>::multilevel(&[NS_CORTEX_ROLES, name.as_bytes(), role.as_bytes()], storage);
+    if granted {
+        roles.save(addr.as_slice(), &true)
+    } else {
+        roles.remove(addr.as_slice());
+        Ok(())
+    }
+}
+
+#[inline(always)]
+pub(crate) fn admin_bucket<'a, S: Storage, T>(storage: &'a mut S, name: &str) -> Bucket<'a, S, T>
+    where T: serde::Serialize + serde::de::DeserializeOwned {
+    Bucket::multilevel(&[NS_CORTEX_ADMIN, name.as_bytes()], storage)
+}
+
+#[inline(always)]
+pub(crate) fn admin_bucket_read<'a, S: ReadonlyStorage, T>(storage: &'a S, name: &str) -> ReadonlyBucket<'a, S, T>
+    where T: serde::Serialize + serde::de::DeserializeOwned {
+    ReadonlyBucket::multilevel(&[NS_CORTEX_ADMIN, name.as_bytes()], storage)
+}
@@ -1,52 +1,147 @@
 use cosmwasm_std::StdError;
-use rhai::{Dynamic, INT};
-use zip_module_resolver::Config;
+use rhai::{Dynamic, INT, Map};
+use zip_module_resolver::{canonical_path, format_path, Config, PathSegment};
+
+use crate::cortex::permissions::Permissions;
+use crate::cortex::schema::{CORTEX_SCHEMA, SCHEMA_SECTIONS, validate_schema};
 
 pub const CFG_KEY_CORTEX_NAME: &'static str = "cortex.name";
 pub const CFG_KEY_CORTEX_VERSION: &'static str = "cortex.version";
+pub const CFG_KEY_EXPORTS_FUNCTIONS: &'static str = "exports.functions";
+pub const CFG_KEY_EXPORTS_CALLERS: &'static str = "exports.callers";
+pub const CFG_KEY_PERMISSIONS: &'static str = "permissions";
+pub const CFG_KEY_REQUIRED_PACKAGES: &'static str = "required_packages";
+pub const CFG_KEY_CONFIG_READONLY: &'static str = "config.readonly";
+
+pub const CALLER_WILDCARD: &'static str = "*";
 
-pub const REQ_STR_KEYS: &'static [&'static str] = &[CFG_KEY_CORTEX_NAME, CFG_KEY_CORTEX_VERSION];
+/// Sections which may never be overridden (in addition to `config.readonly`).
+pub const READONLY_SECTIONS: &'static [&'static str] = &[
+    "cortex", "config", "exports", "global", "permissions", "required_packages",
+];
 
+/// Layered cortex config: bundle defaults, then admin-set overrides (persisted in
+/// storage), except for read-only keys which always come from the bundle.
 #[derive(Debug, Clone)]
 pub struct CortexConfig {
     config: Config,
+    overrides: Option<Config>,
+    readonly: Vec<String>,
 }
 
 impl CortexConfig {
     pub fn new(config: Config) -> Self {
+        let mut readonly: Vec<String> = READONLY_SECTIONS.iter()
+            .map(|s| s.to_string())
+            .collect();
+        readonly.extend(config.get_str_array(CFG_KEY_CONFIG_READONLY)
+            .unwrap_or_default()
+            .iter()
+            .map(|key| canonical_path(key).unwrap_or_else(|_| key.clone())));
+
         Self {
-            config
+            config,
+            overrides: None,
+            readonly,
         }
     }
 
-    pub(crate) fn validate(&mut self) -> Result<(), StdError> {
-        for vk in REQ_STR_KEYS {
-            let val = self.get_str(*vk);
-            if val.is_none() {
-                return Err(StdError::GenericErr {
-                    msg: format!("cortex config missing key '{}'", *vk),
-                    backtrace: None,
-                });
-            }
-        }
+    /// Apply the overrides set by the cortex admin.
+    pub fn set_overrides(&mut self, overrides: Map) -> &mut Self {
+        self.overrides = Some(Config::new(overrides));
+        self
+    }
+
+    /// A key is read-only if it is, is within, or contains a read-only key.
+    pub fn is_readonly(&self, key: &str) -> bool {
+        let key = match canonical_path(key) {
+            Ok(key) => key,
+            Err(_) => return true,
+        };
+        let key = key.as_str();
+
+        self.readonly.iter().any(|ro| key == ro || is_within(key, ro) || is_within(ro, key))
+    }
+
+    /// Is the canonical `key` a read-only key or within one?
+    fn is_readonly_leaf(&self, key: &str) -> bool {
+        self.readonly.iter().any(|ro| key == ro || is_within(key, ro))
+    }
 
-        Ok(())
+    /// Validate the bundle config against the cortex schema, reporting every problem
+    /// at once. Returns any warnings (i.e. unknown keys) on success.
+    ///
+    /// Formats (i.e. of the name and version) are only checked when `deploying`, bundles
+    /// registered before they were enforced must keep loading.
+    pub(crate) fn validate(&mut self, deploying: bool,
+                           file_exists: &dyn Fn(&str) -> bool) -> Result<Vec<String>, StdError> {
+        return validate_schema(&self.config, CORTEX_SCHEMA, SCHEMA_SECTIONS, deploying, file_exists)
+            .into_result();
     }
 
     pub fn get(&self, key: &str) -> Dynamic {
-        return self.config.get(key);
+        let val = self.config.get(key);
+
+        let overrides = match self.overrides.as_ref() {
+            Some(overrides) => overrides,
+            None => return val,
+        };
+        return match canonical_path(key) {
+            Ok(key) => self.merge_override(&key, val, overrides.get(&key)),
+            Err(_) => val,
+        };
+    }
+
+    /// Layer `over` on top of the bundle value `base` at the canonical `key`. Maps are
+    /// merged key by key, so read-only keys within them keep their bundle value.
+    fn merge_override(&self, key: &str, base: Dynamic, over: Dynamic) -> Dynamic {
+        if over.is_unit() || self.is_readonly_leaf(key) {
+            return base;
+        }
+        if over.is::<Map>() != true {
+            // A value may not replace a map containing read-only keys.
+            return if self.is_readonly(key) { base } else { over };
+        }
+
+        let mut merged = base.try_cast::<Map>().unwrap_or_default();
+        for (k, v) in over.cast::<Map>() {
+            let path = format!("{key}.{}", format_path(&[PathSegment::Key(k.to_string())]));
+            let base = merged.get(k.as_str()).cloned().unwrap_or(Dynamic::UNIT);
+
+            let val = self.merge_override(&path, base, v);
+            if !val.is_unit() {
+                merged.insert(k, val);
+            }
+        }
+
+        Dynamic::from_map(merged)
     }
 
     pub fn get_str(&self, key: &str) -> Option<String> {
-        return self.config.get_str(key);
+        let val = self.get(key);
+        if val.is::<String>() != true {
+            return None;
+        }
+
+        Some(val.into_string().unwrap())
     }
 
     pub fn get_int(&self, key: &str) -> Option<INT> {
-        return self.config.get_int(key);
+        let val = self.get(key);
+        if val.is::<INT>() != true {
+            return None;
+        }
+
+        Some(val.as_int().unwrap())
     }
 
     pub fn get_bool(&self, key: &str) -> Option<bool> {
-        return self.config.get_bool(key);
+        let val = self.get(key);
+        if val.is::<bool>() != true {
+            return None;
+        }
+
+        Some(val.as_bool().unwrap())
     }
 
     pub fn cortex_name(&self) -> String {
@@ -56,4 +151,43 @@ impl CortexConfig {
     pub fn cortex_version(&self) -> String {
         return self.get_str(CFG_KEY_CORTEX_VERSION).unwrap();
     }
+
+    /// Capabilities requested by the cortex (subject to approval at deploy time).
+    pub fn permissions(&self) -> Permissions {
+        return Permissions::new(self.config.get_str_array(CFG_KEY_PERMISSIONS)
+            .unwrap_or_default());
+    }
+
+    /// Rhai packages the cortex needs the engine to include.
+    pub fn required_packages(&self) -> Vec<String> {
+        return self.config.get_str_array(CFG_KEY_REQUIRED_PACKAGES)
+            .unwrap_or_default();
+    }
+
+    /// Functions other cortexes may invoke via `cortex_call`.
+    pub fn exported_functions(&self) -> Vec<String> {
+        return self.config.get_str_array(CFG_KEY_EXPORTS_FUNCTIONS)
+            .unwrap_or_default();
+    }
+
+    /// Cortexes permitted to invoke exported functions ("*" allows any).
+    pub fn allowed_callers(&self) -> Vec<String> {
+        return self.config.get_str_array(CFG_KEY_EXPORTS_CALLERS)
+            .unwrap_or_default();
+    }
+
+    pub fn is_exported(&self, fn_name: &str) -> bool {
+        self.exported_functions().iter().any(|f| f == fn_name)
+    }
+
+    pub fn is_caller_allowed(&self, caller: &str) -> bool {
+        self.allowed_callers().iter()
+            .any(|c| c == CALLER_WILDCARD || c == caller)
+    }
+}
+
+/// Is the canonical `key` nested under the canonical `parent`?
+#[inline(always)]
+fn is_within(key: &str, parent: &str) -> bool {
+    key.starts_with(parent) && key[parent.len()..].starts_with('.')
 }
@@ -1 +1,5 @@
-pub(crate) mod config;
\ No newline at end of file
+pub(crate) mod admin;
+pub(crate) mod config;
+pub(crate) mod permissions;
+pub(crate) mod registry;
+pub(crate) mod schema;
\ No newline at end of file
@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+
+use cosmwasm_std::StdError;
+
+pub const PERM_STORAGE_WRITE: &'static str = "storage.write";
+pub const PERM_QUERY_CHAIN: &'static str = "query.chain";
+pub const PERM_STAKING: &'static str = "staking";
+pub const PERM_CORTEX_CALL: &'static str = "cortex.call";
+pub const PERM_BANK_SEND: &'static str = "bank.send";
+pub const PERM_WASM_EXECUTE: &'static str = "wasm.execute";
+
+/// Every permission gates host functions in `OmnibusEngine::register_functions`, add new
+/// permissions here only along with the functions they grant.
+pub const KNOWN_PERMISSIONS: &'static [&'static str] = &[
+    PERM_STORAGE_WRITE, PERM_QUERY_CHAIN, PERM_STAKING, PERM_CORTEX_CALL, PERM_BANK_SEND,
+    PERM_WASM_EXECUTE,
+];
+
+/// The set of capabilities a cortex has been granted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Permissions {
+    granted: BTreeSet<String>,
+}
+
+impl Permissions {
+    pub fn new(perms: Vec<String>) -> Self {
+        Self {
+            granted: perms.into_iter().collect(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn has(&self, perm: &str) -> bool {
+        self.granted.contains(perm)
+    }
+
+    #[inline(always)]
+    pub fn to_vec(&self) -> Vec<String> {
+        self.granted.iter().cloned().collect()
+    }
+
+    /// Permissions present in both sets.
+    pub fn intersect(&self, other: &Permissions) -> Permissions {
+        Self {
+            granted: self.granted.intersection(&other.granted).cloned().collect(),
+        }
+    }
+
+    /// Ensure every permission in this set has been approved.
+    pub fn check_approved(&self, approved: &Permissions) -> Result<(), StdError> {
+        let missing: Vec<&str> = self.granted.difference(&approved.granted)
+            .map(|p| p.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(StdError::GenericErr {
+                msg: format!("cortex permissions not approved: {}", missing.join(", ")),
+                backtrace: None,
+            });
+        }
+
+        Ok(())
+    }
+}
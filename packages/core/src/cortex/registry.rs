@@ -0,0 +1,105 @@
+use cosmwasm_std::{from_slice, ReadonlyStorage, StdError, StdResult, Storage, to_vec};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+use rhai::Engine;
+use zip_module_resolver::{bundle_hash, BundleHash, Config, ZipModuleResolver};
+
+use crate::cortex::permissions::Permissions;
+
+pub const NS_CORTEX_BUNDLES: &'static [u8] = b"cortex_bundles";
+pub const NS_CORTEX_HASHES: &'static [u8] = b"cortex_hashes";
+pub const NS_CORTEX_GRANTS: &'static [u8] = b"cortex_grants";
+pub const NS_CORTEX_DATA: &'static [u8] = b"cortex_data";
+
+/// Store the bundle for a cortex so it can later be loaded by name (i.e. via `cortex_call`).
+pub fn store_bundle<S: Storage>(storage: &mut S, name: &str, bytes: &[u8]) {
+    PrefixedStorage::new(NS_CORTEX_BUNDLES, storage)
+        .set(name.as_bytes(), bytes);
+    PrefixedStorage::new(NS_CORTEX_HASHES, storage)
+        .set(name.as_bytes(), &bundle_hash(bytes));
+}
+
+pub fn load_bundle<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Vec<u8>> {
+    return match ReadonlyPrefixedStorage::new(NS_CORTEX_BUNDLES, storage)
+        .get(name.as_bytes()) {
+        None => Err(StdError::not_found(format!("cortex '{name}'"))),
+        Some(bytes) => Ok(bytes)
+    };
+}
+
+/// The config of the bundle registered for a cortex (without any admin overrides).
+pub fn load_bundle_config<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Config> {
+    let mut resolver = ZipModuleResolver::new();
+    resolver.load_from_bytes(load_bundle(storage, name)?)
+        .and_then(|_| resolver.load_config(&Engine::new_raw()))
+        .map_err(|err| StdError::generic_err(format!("failed to load config of cortex '{name}': {err}")))?;
+
+    Ok(resolver.config())
+}
+
+/// The hash of the bundle registered for a cortex.
+pub fn load_bundle_hash<S: ReadonlyStorage>(storage: &S, name: &str) -> Option<BundleHash> {
+    let hash = ReadonlyPrefixedStorage::new(NS_CORTEX_HASHES, storage)
+        .get(name.as_bytes())?;
+
+    let mut out = BundleHash::default();
+    if hash.len() != out.len() {
+        return None;
+    }
+    out.copy_from_slice(&hash);
+
+    Some(out)
+}
+
+pub fn has_bundle<S: ReadonlyStorage>(storage: &S, name: &str) -> bool {
+    ReadonlyPrefixedStorage::new(NS_CORTEX_BUNDLES, storage)
+        .get(name.as_bytes()).is_some()
+}
+
+/// Store the permissions approved for a cortex at deploy time.
+pub fn store_grants<S: Storage>(storage: &mut S, name: &str, grants: &Permissions) -> StdResult<()> {
+    PrefixedStorage::new(NS_CORTEX_GRANTS, storage)
+        .set(name.as_bytes(), &to_vec(&grants.to_vec())?);
+
+    Ok(())
+}
+
+/// Load the approved permissions for a cortex (none if it was never granted any).
+pub fn load_grants<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Permissions> {
+    return match ReadonlyPrefixedStorage::new(NS_CORTEX_GRANTS, storage)
+        .get(name.as_bytes()) {
+        None => Ok(Permissions::default()),
+        Some(bytes) => Ok(Permissions::new(from_slice(&bytes)?))
+    };
+}
+
+/// Storage for the data of a single cortex, isolated from all other cortexes.
+#[inline(always)]
+pub fn cortex_storage<'a, S: Storage>(storage: &'a mut S, name: &str) -> PrefixedStorage<'a, S> {
+    PrefixedStorage::multilevel(&[NS_CORTEX_DATA, name.as_bytes()], storage)
+}
+
+#[inline(always)]
+pub fn cortex_storage_read<'a, S: ReadonlyStorage>(storage: &'a S, name: &str) -> ReadonlyPrefixedStorage<'a, S> {
+    ReadonlyPrefixedStorage::multilevel(&[NS_CORTEX_DATA, name.as_bytes()], storage)
+}
+
+/// Move keys stored before cortex storage was namespaced (at the root of contract storage)
+/// into the storage of `name`. Namespaced keys (which begin with a zero byte length prefix)
+/// are never moved, nor are keys already present in the cortex storage overwritten.
+pub fn migrate_legacy_keys<S: Storage>(storage: &mut S, name: &str, keys: &[Vec<u8>]) -> StdResult<()> {
+    for key in keys {
+        if key.first().map_or(true, |b| *b == 0) {
+            return Err(StdError::generic_err(format!("legacy key {:?} is namespaced", key)));
+        }
+        if cortex_storage_read(storage, name).get(key).is_some() {
+            return Err(StdError::generic_err(format!("key {:?} already exists in cortex '{name}'", key)));
+        }
+
+        let value = storage.get(key)
+            .ok_or_else(|| StdError::not_found(format!("legacy key {:?}", key)))?;
+        cortex_storage(storage, name).set(key, &value);
+        storage.remove(key);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,367 @@
+use cosmwasm_std::StdError;
+use rhai::{Array, Dynamic, INT, Map};
+use zip_module_resolver::{CFG_KEY_ASSETS_DIR, CFG_KEY_ASSETS_MAX_SIZE, CFG_KEY_GLOBAL_CONSTANTS,
+                          CFG_KEY_GLOBAL_ENTRYPOINTS, Config};
+
+use crate::cortex::config::{CFG_KEY_CONFIG_READONLY, CFG_KEY_CORTEX_NAME, CFG_KEY_CORTEX_VERSION,
+                            CFG_KEY_EXPORTS_CALLERS, CFG_KEY_EXPORTS_FUNCTIONS, CFG_KEY_PERMISSIONS,
+                            CFG_KEY_REQUIRED_PACKAGES};
+use crate::cortex::permissions::KNOWN_PERMISSIONS;
+use crate::rhai::packages::selection::PackageKind;
+
+// Per-endpoint entrypoint groups (`entrypoints.<endpoint>`).
+pub const CFG_KEY_ENTRYPOINTS_DEPLOY: &'static str = "entrypoints.deploy";
+pub const CFG_KEY_ENTRYPOINTS_HANDLE: &'static str = "entrypoints.handle";
+pub const CFG_KEY_ENTRYPOINTS_QUERY: &'static str = "entrypoints.query";
+
+pub const MAX_IDENTIFIER_LEN: usize = 64;
+
+/// The type a config value must have (one per `Config` getter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Str,
+    Int,
+    Bool,
+    Map,
+    Array,
+    StrArray,
+}
+
+/// Additional checks applied to string values (or each element of a string array).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldFormat {
+    Any,
+    Semver,
+    Identifier,
+    Permission,
+    Package,
+    /// A script path which must exist in the bundle.
+    ScriptPath,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaField {
+    pub key: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub format: FieldFormat,
+}
+
+impl SchemaField {
+    pub const fn new(key: &'static str, kind: FieldKind, required: bool,
+                     format: FieldFormat) -> Self {
+        Self { key, kind, required, format }
+    }
+}
+
+/// The schema of the reserved cortex config sections.
+pub const CORTEX_SCHEMA: &'static [SchemaField] = &[
+    SchemaField::new(CFG_KEY_CORTEX_NAME, FieldKind::Str, true, FieldFormat::Identifier),
+    SchemaField::new(CFG_KEY_CORTEX_VERSION, FieldKind::Str, true, FieldFormat::Semver),
+    SchemaField::new(CFG_KEY_PERMISSIONS, FieldKind::StrArray, false, FieldFormat::Permission),
+    SchemaField::new(CFG_KEY_REQUIRED_PACKAGES, FieldKind::StrArray, false, FieldFormat::Package),
+    SchemaField::new(CFG_KEY_EXPORTS_FUNCTIONS, FieldKind::StrArray, false, FieldFormat::Any),
+    SchemaField::new(CFG_KEY_EXPORTS_CALLERS, FieldKind::StrArray, false, FieldFormat::Any),
+    SchemaField::new(CFG_KEY_CONFIG_READONLY, FieldKind::StrArray, false, FieldFormat::Any),
+    SchemaField::new(CFG_KEY_GLOBAL_ENTRYPOINTS, FieldKind::StrArray, false, FieldFormat::ScriptPath),
+    SchemaField::new(CFG_KEY_GLOBAL_CONSTANTS, FieldKind::Map, false, FieldFormat::Any),
+    SchemaField::new(CFG_KEY_ENTRYPOINTS_DEPLOY, FieldKind::StrArray, false, FieldFormat::ScriptPath),
+    SchemaField::new(CFG_KEY_ENTRYPOINTS_HANDLE, FieldKind::StrArray, false, FieldFormat::ScriptPath),
+    SchemaField::new(CFG_KEY_ENTRYPOINTS_QUERY, FieldKind::StrArray, false, FieldFormat::ScriptPath),
+    SchemaField::new(CFG_KEY_ASSETS_DIR, FieldKind::Str, false, FieldFormat::Any),
+    SchemaField::new(CFG_KEY_ASSETS_MAX_SIZE, FieldKind::Int, false, FieldFormat::Any),
+];
+
+/// Sections owned by the schema, any key within them not in the schema is reported.
+pub const SCHEMA_SECTIONS: &'static [&'static str] = &["cortex", "exports", "config", "global", "entrypoints", "assets"];
+
+/// The outcome of validating a config against a schema.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl SchemaReport {
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Convert to a single error listing every problem found.
+    pub fn into_result(self) -> Result<Vec<String>, StdError> {
+        if self.is_valid() {
+            return Ok(self.warnings);
+        }
+
+        Err(StdError::GenericErr {
+            msg: format!("cortex config is invalid: {}", self.errors.join("; ")),
+            backtrace: None,
+        })
+    }
+}
+
+/// Validate `config` against `schema`, `file_exists` is used to check script paths.
+///
+/// Formats are only checked if `check_formats` is set, so bundles registered before a
+/// format was tightened keep loading.
+pub fn validate_schema(
+    config: &Config,
+    schema: &[SchemaField],
+    sections: &[&str],
+    check_formats: bool,
+    file_exists: &dyn Fn(&str) -> bool,
+) -> SchemaReport {
+    let mut report = SchemaReport::default();
+
+    for field in schema {
+        let field = if check_formats { *field } else { SchemaField { format: FieldFormat::Any, ..*field } };
+        validate_field(config, &field, file_exists, &mut report);
+    }
+
+    for section in sections {
+        let val = config.get(section);
+        if val.is_unit() {
+            continue;
+        }
+        if val.is::<Map>() != true {
+            report.errors.push(format!("'{section}' must be a map"));
+            continue;
+        }
+
+        let map = val.read_lock::<Map>().unwrap();
+        for key in map.keys() {
+            let path = format!("{section}.{key}");
+            let known = schema.iter().any(|f| {
+                f.key == path || f.key.starts_with(&format!("{path}."))
+            });
+            if !known {
+                report.warnings.push(format!("unknown key '{path}'"));
+            }
+        }
+    }
+
+    report
+}
+
+fn validate_field(
+    config: &Config,
+    field: &SchemaField,
+    file_exists: &dyn Fn(&str) -> bool,
+    report: &mut SchemaReport,
+) {
+    let key = field.key;
+    let val = config.get(key);
+    if val.is_unit() {
+        if field.required {
+            report.errors.push(format!("missing key '{key}'"));
+        }
+        return;
+    }
+
+    match field.kind {
+        FieldKind::Str => {
+            if val.is::<String>() != true {
+                report.errors.push(type_mismatch(key, "string", &val));
+                return;
+            }
+            if let Some(err) = check_format(field.format, &val.into_string().unwrap(), file_exists) {
+                report.errors.push(format!("'{key}' {err}"));
+            }
+        }
+        FieldKind::Int => {
+            if val.is::<INT>() != true {
+                report.errors.push(type_mismatch(key, "integer", &val));
+            }
+        }
+        FieldKind::Bool => {
+            if val.is::<bool>() != true {
+                report.errors.push(type_mismatch(key, "bool", &val));
+            }
+        }
+        FieldKind::Map => {
+            if val.is::<Map>() != true {
+                report.errors.push(type_mismatch(key, "map", &val));
+            }
+        }
+        FieldKind::Array => {
+            if val.is::<Array>() != true {
+                report.errors.push(type_mismatch(key, "array", &val));
+            }
+        }
+        FieldKind::StrArray => {
+            if val.is::<Array>() != true {
+                report.errors.push(type_mismatch(key, "array of strings", &val));
+                return;
+            }
+            let arr = val.read_lock::<Array>().unwrap();
+            for (i, item) in arr.iter().enumerate() {
+                if item.is::<String>() != true {
+                    report.errors.push(type_mismatch(&format!("{key}[{i}]"), "string", item));
+                    continue;
+                }
+                let item = item.clone().into_string().unwrap();
+                if let Some(err) = check_format(field.format, &item, file_exists) {
+                    report.errors.push(format!("'{key}[{i}]' {err}"));
+                }
+            }
+        }
+    }
+}
+
+fn type_mismatch(key: &str, expected: &str, val: &Dynamic) -> String {
+    format!("'{key}' must be a {expected} (found {})", val.type_name())
+}
+
+fn check_format(format: FieldFormat, val: &str, file_exists: &dyn Fn(&str) -> bool) -> Option<String> {
+    return match format {
+        FieldFormat::Any => None,
+        FieldFormat::Semver if !is_semver(val) => {
+            Some(format!("'{val}' is not a valid semver version (e.g. 1.0.0)"))
+        }
+        FieldFormat::Identifier if !is_identifier(val) => {
+            Some(format!("'{val}' is not a valid identifier ([a-z0-9_-], starting with a letter, max {MAX_IDENTIFIER_LEN})"))
+        }
+        FieldFormat::Permission if !KNOWN_PERMISSIONS.contains(&val) => {
+            Some(format!("'{val}' is not a known permission"))
+        }
+        FieldFormat::Package if PackageKind::from_name(val).is_none() => {
+            Some(format!("'{val}' is not a known rhai package"))
+        }
+        FieldFormat::ScriptPath if !file_exists(val) => {
+            Some(format!("'{val}' does not exist in the bundle"))
+        }
+        _ => None,
+    };
+}
+
+/// MAJOR.MINOR.PATCH with an optional -pre-release and +build suffix.
+pub fn is_semver(val: &str) -> bool {
+    let (val, build) = match val.split_once('+') {
+        Some((v, b)) => (v, Some(b)),
+        None => (val, None),
+    };
+    let (core, pre) = match val.split_once('-') {
+        Some((c, p)) => (c, Some(p)),
+        None => (val, None),
+    };
+
+    let is_ident_list = |s: &str| {
+        !s.is_empty() && s.split('.').all(|p| {
+            !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    };
+    if !pre.map_or(true, is_ident_list) || !build.map_or(true, is_ident_list) {
+        return false;
+    }
+
+    let parts = core.split('.').collect::<Vec<_>>();
+    parts.len() == 3 && parts.iter().all(|p| {
+        !p.is_empty()
+            && p.chars().all(|c| c.is_ascii_digit())
+            && (p.len() == 1 || !p.starts_with('0'))
+    })
+}
+
+/// A lowercase identifier suitable for use as a storage namespace.
+pub fn is_identifier(val: &str) -> bool {
+    let mut chars = val.chars();
+    let first_ok = chars.next().map_or(false, |c| c.is_ascii_lowercase());
+
+    first_ok
+        && val.len() <= MAX_IDENTIFIER_LEN
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rhai::json::from_json_str;
+
+    use super::*;
+
+    fn config(json: &str) -> Config {
+        Config::new(from_json_str(json).unwrap().cast::<Map>())
+    }
+
+    fn validate(json: &str, check_formats: bool) -> SchemaReport {
+        validate_schema(&config(json), CORTEX_SCHEMA, SCHEMA_SECTIONS, check_formats,
+                        &|path: &str| path == "main")
+    }
+
+    #[test]
+    fn semver() {
+        for val in &["0.0.0", "1.2.3", "10.20.30", "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0+build.5",
+                     "1.0.0-rc-1+sha.abc"] {
+            assert!(is_semver(val), "'{val}' should be valid");
+        }
+        for val in &["", "1", "1.0", "1.0.0.0", "01.0.0", "1.02.0", "a.b.c", "1.0.0-", "1.0.0+",
+                     "1.0.0-alpha..1", "1.0.0-al_pha", "v1.0.0"] {
+            assert!(!is_semver(val), "'{val}' should be invalid");
+        }
+    }
+
+    #[test]
+    fn identifier() {
+        for val in &["a", "counter", "my-cortex_2"] {
+            assert!(is_identifier(val), "'{val}' should be valid");
+        }
+        for val in &["", "MyCortex", "2cortex", "_cortex", "my cortex", "my.cortex"] {
+            assert!(!is_identifier(val), "'{val}' should be invalid");
+        }
+        assert!(is_identifier(&"a".repeat(MAX_IDENTIFIER_LEN)));
+        assert!(!is_identifier(&"a".repeat(MAX_IDENTIFIER_LEN + 1)));
+    }
+
+    #[test]
+    fn reports_every_error_at_once() {
+        let report = validate(r#"{"cortex":{"name":"MyCortex","version":"1.0"},
+                                  "permissions":["storage.write","teleport"],
+                                  "global":{"entrypoints":["main","missing"]},"assets":{"max_size":"big"}}"#,
+                              true);
+
+        assert_eq!(report.errors, vec![
+            "'cortex.name' 'MyCortex' is not a valid identifier ([a-z0-9_-], starting with a letter, max 64)".to_string(),
+            "'cortex.version' '1.0' is not a valid semver version (e.g. 1.0.0)".to_string(),
+            "'permissions[1]' 'teleport' is not a known permission".to_string(),
+            "'global.entrypoints[1]' 'missing' does not exist in the bundle".to_string(),
+            "'assets.max_size' must be a integer (found string)".to_string(),
+        ]);
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn reports_missing_and_mistyped_keys() {
+        let report = validate(r#"{"cortex":{"version":1},"exports":{"functions":"get"}}"#, true);
+
+        assert_eq!(report.errors, vec![
+            "missing key 'cortex.name'".to_string(),
+            "'cortex.version' must be a string (found i32)".to_string(),
+            "'exports.functions' must be a array of strings (found string)".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn warns_about_unknown_keys() {
+        let report = validate(r#"{"cortex":{"name":"counter","version":"1.0.0","author":"me"},
+                                  "global":{"entrypoints":["main"],"extra":1},"custom":{"any":1}}"#,
+                              true);
+
+        assert!(report.is_valid(), "unexpected errors: {:?}", report.errors);
+        assert_eq!(report.into_result().unwrap(), vec![
+            "unknown key 'cortex.author'".to_string(),
+            "unknown key 'global.extra'".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn skips_formats_unless_asked() {
+        let json = r#"{"cortex":{"name":"MyCortex","version":"1.0"},"global":{"entrypoints":["missing"]}}"#;
+
+        assert_eq!(validate(json, true).errors.len(), 3);
+        assert!(validate(json, false).is_valid());
+
+        // Types are still checked.
+        assert_eq!(validate(r#"{"cortex":{"name":1,"version":"1.0"}}"#, false).errors,
+                   vec!["'cortex.name' must be a string (found i32)".to_string()]);
+    }
+}
@@ -3,15 +3,29 @@ extern crate zip_module_resolver;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use cosmwasm_std::{Api, Env, Extern, HandleResponse, Querier, StdError, StdResult, Storage};
+use cosmwasm_std::{Api, CosmosMsg, Env, Extern, HandleResponse, HumanAddr, Querier, StdError, StdResult, Storage, to_vec};
 #[cfg(feature = "debug-print")]
 use cosmwasm_std::{debug_print};
-use rhai::{AST, Caches, Dynamic, Engine, EvalAltResult, GlobalRuntimeState, ImmutableString, Module, Scope, ScriptFnDef, Shared};
+use rhai::{Array, AST, Blob, Caches, Dynamic, Engine, EvalAltResult, GlobalRuntimeState, ImmutableString, Module, Scope, ScriptFnDef, Shared};
 use rhai::packages::Package;
-use zip_module_resolver::{ZipModuleResolver};
+use zip_module_resolver::{canonical_path, ModuleCacheStats, SharedModuleCache, ZipModuleResolver};
 
 use crate::CortexConfig;
+use crate::cortex::admin::{authorize_deploy, is_paused, load_config_overrides};
+use crate::cortex::permissions::{PERM_BANK_SEND, PERM_CORTEX_CALL, PERM_QUERY_CHAIN, PERM_STORAGE_WRITE,
+                                 PERM_WASM_EXECUTE, Permissions};
+#[cfg(feature = "staking")]
+use crate::cortex::permissions::PERM_STAKING;
+use crate::cortex::registry::{cortex_storage, load_bundle, load_bundle_hash, load_grants, store_bundle, store_grants};
+use crate::rhai::functions::admin::register_admin_functions;
+use crate::rhai::functions::api::register_api_functions;
+use crate::rhai::functions::assets::register_asset_functions;
+use crate::rhai::functions::msgs::{Messages, register_bank_functions, register_wasm_functions};
+use crate::rhai::functions::querier::register_querier_functions;
+#[cfg(feature = "staking")]
+use crate::rhai::functions::querier::register_staking_functions;
 use crate::rhai::packages::pkg_std::StandardPackage;
+use crate::rhai::packages::selection::PackageKind;
 
 pub const ENDPOINT_FN_DEPLOY: &'static str = "deploy";
 pub const ENDPOINT_FN_HANDLE: &'static str = "handle";
@@ -19,14 +33,25 @@ pub const ENDPOINT_FN_QUERY: &'static str = "query";
 
 pub const ENDPOINT_METHODS: &'static [&'static str] = &[ENDPOINT_FN_DEPLOY, ENDPOINT_FN_HANDLE, ENDPOINT_FN_QUERY];
 
+/// Maximum nesting of `cortex_call` (the top level cortex is depth 0).
+pub const MAX_CORTEX_CALL_DEPTH: u32 = 8;
+
 pub struct OmnibusEngine<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> {
     rh_engine: Engine,
     rh_caches: Option<Caches>,
     rh_global: Option<GlobalRuntimeState<'static>>,
-    rh_resolver: RefCell<Option<ZipModuleResolver>>,
+    rh_resolver: Rc<RefCell<Option<ZipModuleResolver>>>,
     rh_ast: Option<AST>,
+    packages: Vec<PackageKind>,
     deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Option<Env>,
     cfg: Option<CortexConfig>,
+    approved: Option<Permissions>,
+    permissions: Permissions,
+    call_depth: u32,
+    endpoint: Option<&'static str>,
+    module_cache: Option<SharedModuleCache>,
+    messages: Messages,
     #[cfg(any(feature = "debug-print", feature = "test-print"))]
     debug_label: String,
 }
@@ -42,6 +67,19 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
         engine
     }
 
+    /// Create an engine including only the packages specified.
+    pub fn new_with_packages(
+        deps: Rc<RefCell<Extern<S, A, Q>>>,
+        packages: &[PackageKind],
+    ) -> Result<Self, StdError> {
+        let mut engine = Self::new_raw(deps);
+
+        engine.register_packages(packages)?;
+        engine.rh_engine.set_strict_variables(true);
+
+        Ok(engine)
+    }
+
     #[inline(always)]
     pub fn new_raw(
         deps: Rc<RefCell<Extern<S, A, Q>>>,
@@ -50,10 +88,18 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
             rh_engine: Engine::new_raw(),
             rh_caches: None,
             rh_global: None,
-            rh_resolver: RefCell::new(None),
+            rh_resolver: Rc::new(RefCell::new(None)),
             rh_ast: None,
+            packages: Vec::new(),
             deps,
+            env: None,
             cfg: None,
+            approved: None,
+            permissions: Permissions::default(),
+            call_depth: 0,
+            endpoint: None,
+            module_cache: None,
+            messages: Rc::new(RefCell::new(Vec::new())),
             #[cfg(any(feature = "debug-print", feature = "test-print"))]
             debug_label: "None".to_string(),
         }
@@ -76,9 +122,30 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
     #[inline(always)]
     pub fn register_modules(&mut self) -> &mut Self {
         self.register_global_module(StandardPackage::new().as_shared_module());
+        self.packages.extend(PackageKind::enabled());
         self
     }
 
+    /// Register a selection of packages (instead of the standard package), `Core`
+    /// is always included.
+    pub fn register_packages(&mut self, packages: &[PackageKind]) -> Result<&mut Self, StdError> {
+        let mut selected = packages.to_vec();
+        if !selected.contains(&PackageKind::Core) && !self.has_package(PackageKind::Core) {
+            selected.insert(0, PackageKind::Core);
+        }
+
+        let lib = packages_module(&selected)?;
+        self.packages.extend(selected);
+        self.register_global_module(lib.into());
+
+        Ok(self)
+    }
+
+    #[inline(always)]
+    pub fn has_package(&self, package: PackageKind) -> bool {
+        self.packages.contains(&package)
+    }
+
     #[inline(always)]
     pub fn register_global_module(&mut self, module: Shared<Module>) -> &mut Self {
         self.rh_engine.register_global_module(module);
@@ -118,36 +185,100 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
         self
     }
 
+    /// Registers only the host functions the cortex has been granted.
     pub fn register_functions(&mut self) -> &mut Self {
+        register_api_functions(&mut self.rh_engine, self.deps.clone());
+        register_admin_functions(&mut self.rh_engine, self.deps.clone(), self.cortex_name());
+        register_asset_functions(&mut self.rh_engine, self.rh_resolver.clone());
+        self.register_config_functions();
+
+        if self.permissions.has(PERM_STORAGE_WRITE) {
+            self.register_storage_write_functions();
+        }
+        if self.permissions.has(PERM_CORTEX_CALL) {
+            self.register_cortex_functions();
+        }
+        if self.permissions.has(PERM_QUERY_CHAIN) {
+            register_querier_functions(&mut self.rh_engine, self.deps.clone());
+        }
+        if self.permissions.has(PERM_BANK_SEND) {
+            register_bank_functions(&mut self.rh_engine, self.env.clone(), self.messages.clone());
+        }
+        if self.permissions.has(PERM_WASM_EXECUTE) {
+            register_wasm_functions(&mut self.rh_engine, self.messages.clone());
+        }
+        #[cfg(feature = "staking")]
+        if self.permissions.has(PERM_STAKING) {
+            register_staking_functions(&mut self.rh_engine, self.deps.clone());
+        }
+
+        self
+    }
+
+    pub fn register_config_functions(&mut self) -> &mut Self {
+        let cfg = self.cfg.clone()
+            .expect("cortex config must be loaded before use");
+        self.rh_engine.register_fn("config", move |key: &str| -> Dynamic {
+            cfg.get(key)
+        });
+
+        self
+    }
+
+    pub fn register_storage_write_functions(&mut self) -> &mut Self {
         // TODO: This is a mess and will change a lot (this is just for testing).
+        let name = self.cortex_name();
+
         let deps = self.deps.clone();
+        let ns = name.clone();
         self.rh_engine.register_fn("storage_set", move |key: &str, val: &str| {
-            RefCell::borrow_mut(&*deps).storage.set(key.as_bytes(), val.as_bytes());
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(key.as_bytes(), val.as_bytes());
         });
 
         let deps = self.deps.clone();
+        let ns = name.clone();
         self.rh_engine.register_fn("storage_set", move |key: &str, val: &[u8]| {
-            RefCell::borrow_mut(&*deps).storage.set(key.as_bytes(), val);
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(key.as_bytes(), val);
+        });
+
+        let deps = self.deps.clone();
+        let ns = name.clone();
+        self.rh_engine.register_fn("storage_set", move |key: Blob, val: &str| {
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(&key, val.as_bytes());
+        });
+
+        let deps = self.deps.clone();
+        let ns = name.clone();
+        self.rh_engine.register_fn("storage_set", move |key: Blob, val: &[u8]| {
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(&key, val);
         });
 
         let deps = self.deps.clone();
+        let ns = name.clone();
         self.rh_engine.register_result_fn("storage_set", move |key_path: &mut Vec<Dynamic>, val: &str| -> Result<(), Box<EvalAltResult>> {
             let key = expand_key_path(key_path).map_err(|err| {
                 return format!("error during storage set: {err}");
             })?;
 
-            RefCell::borrow_mut(&*deps).storage.set(key.as_bytes(), val.as_bytes());
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(&key, val.as_bytes());
 
             Ok(())
         });
 
         let deps = self.deps.clone();
+        let ns = name.clone();
         self.rh_engine.register_result_fn("storage_set", move |key_path: &mut Vec<Dynamic>, val: &[u8]| -> Result<(), Box<EvalAltResult>> {
             let key = expand_key_path(key_path).map_err(|err| {
                 return format!("error during storage set: {err}");
             })?;
 
-            RefCell::borrow_mut(&*deps).storage.set(key.as_bytes(), val);
+            cortex_storage(&mut RefCell::borrow_mut(&*deps).storage, &ns)
+                .set(&key, val);
 
             Ok(())
         });
@@ -155,6 +286,30 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
         self
     }
 
+    pub fn register_cortex_functions(&mut self) -> &mut Self {
+        let deps = self.deps.clone();
+        let env = self.env.clone();
+        let caller = self.cortex_name();
+        let depth = self.call_depth;
+        let cache = self.module_cache.clone();
+        let packages = self.packages.clone();
+        let messages = self.messages.clone();
+        self.rh_engine.register_result_fn("cortex_call", move |callee: &str, fn_name: &str, args: Array| -> Result<Dynamic, Box<EvalAltResult>> {
+            let env = match env.as_ref() {
+                None => return Err("error during cortex call: no env available".into()),
+                Some(env) => env.clone()
+            };
+
+            Self::call_cortex(deps.clone(), env, &caller, depth + 1, cache.clone(), &packages,
+                              messages.clone(), callee, fn_name, args.to_vec())
+                .map_err(|err| {
+                    return format!("error during cortex call: {err}").into();
+                })
+        });
+
+        self
+    }
+
     #[inline(always)]
     pub fn loaded_core(&mut self) -> bool {
         self.rh_resolver.borrow().is_some()
@@ -165,6 +320,10 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
 
     pub fn load_core(&mut self, bytes: Vec<u8>, env: Env) -> Result<(), StdError> {
         let mut resolver = ZipModuleResolver::new();
+        resolver.set_entrypoint_group(self.endpoint.map(|e| e.to_string()));
+        resolver.set_shared_cache(self.module_cache.clone());
+        // Modules are compiled with ENV in scope, so only share them for the same env.
+        resolver.set_scope_key(to_vec(&env)?);
         resolver.load_from_bytes(bytes)
             .map_err(|err| {
                 return StdError::GenericErr {
@@ -173,8 +332,15 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
                 };
             })?;
 
-        self.rh_resolver = RefCell::new(Some(resolver.clone()));
-        self.rh_engine.set_module_resolver(resolver);
+        #[cfg(feature = "baked-consts-only")]
+        if !resolver.has_baked_consts() {
+            return Err(StdError::GenericErr {
+                msg: format!("failed to load core: only cores with baked consts are accepted"),
+                backtrace: None,
+            });
+        }
+
+        self.rh_resolver = Rc::new(RefCell::new(Some(resolver)));
 
         self.init_core(env)?;
 
@@ -182,6 +348,8 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
     }
 
     pub fn init_core(&mut self, env: Env) -> Result<(), StdError> {
+        self.env = Some(env.clone());
+
         {
             let mut rc_resolver = RefCell::borrow_mut(&self.rh_resolver);
             let resolver = rc_resolver.as_mut().unwrap();
@@ -212,6 +380,9 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
                     }
                 }
             }
+
+            // Set after init so imported modules see the globals and ENV in its scope.
+            self.rh_engine.set_module_resolver(resolver.clone());
         }
 
         self.load_config()?;
@@ -260,7 +431,61 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
         let resolver = rc_resolver.as_mut().unwrap();
 
         let mut cfg = CortexConfig::new(resolver.config());
-        cfg.validate()?;
+        let resolver_ro = &*resolver;
+        // Only a deploy (which has approved permissions) registers a new bundle.
+        let _warnings = cfg.validate(self.approved.is_some(), &|path: &str| {
+            resolver_ro.get_source_path(path, None)
+                .map_or(false, |path| resolver_ro.has_file(path))
+        })?;
+
+        #[cfg(any(feature = "debug-print", feature = "test-print"))]
+        for warning in &_warnings {
+            #[cfg(feature = "debug-print")]
+            debug_print!("CORTEX[{}][warn ]: config {}", cfg.cortex_name(), warning);
+
+            #[cfg(feature = "test-print")]
+            println!("CORTEX[{}][warn ]: config {}", cfg.cortex_name(), warning);
+        }
+
+        // Grants, storage and admin state are keyed by the name the bundle declares, so
+        // outside of deploy only the bundle registered under that name may claim it.
+        if self.approved.is_none() {
+            let registered = load_bundle_hash(&RefCell::borrow(&*self.deps).storage,
+                                              &cfg.cortex_name());
+            if registered.is_none() || registered.as_ref() != resolver_ro.bundle_hash() {
+                return Err(StdError::GenericErr {
+                    msg: format!("bundle is not the registered core for cortex '{}'", cfg.cortex_name()),
+                    backtrace: None,
+                });
+            }
+        }
+
+        if let Some(overrides) = load_config_overrides(&RefCell::borrow(&*self.deps).storage,
+                                                       &cfg.cortex_name())? {
+            cfg.set_overrides(overrides);
+        }
+
+        for name in cfg.required_packages() {
+            let available = PackageKind::from_name(&name)
+                .map_or(false, |pkg| self.has_package(pkg));
+            if !available {
+                return Err(StdError::GenericErr {
+                    msg: format!("cortex requires rhai package '{name}' which is not included"),
+                    backtrace: None,
+                });
+            }
+        }
+
+        // Only permissions both requested by the config and approved by the admin apply.
+        let requested = cfg.permissions();
+        let approved = match self.approved.as_ref() {
+            Some(approved) => {
+                requested.check_approved(approved)?;
+                approved.clone()
+            }
+            None => load_grants(&RefCell::borrow(&*self.deps).storage, &cfg.cortex_name())?
+        };
+        self.permissions = requested.intersect(&approved);
 
         #[cfg(any(feature = "debug-print", feature = "test-print"))]
         {
@@ -272,6 +497,104 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
         Ok(())
     }
 
+    #[inline(always)]
+    pub fn config(&self) -> Option<&CortexConfig> {
+        self.cfg.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn cortex_name(&self) -> String {
+        self.cfg.as_ref()
+            .expect("cortex config must be loaded before use")
+            .cortex_name()
+    }
+
+    /// Approve the permissions a cortex may be granted (used at deploy time,
+    /// otherwise the grants stored during deploy apply).
+    #[inline(always)]
+    pub fn approve_permissions(&mut self, approved: Vec<String>) -> &mut Self {
+        self.approved = Some(Permissions::new(approved));
+        self
+    }
+
+    #[inline(always)]
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Only compile the entrypoints for `endpoint` (plus global), must be called before
+    /// `load_core`. All entrypoints are compiled if not set.
+    #[inline(always)]
+    pub fn set_endpoint(&mut self, endpoint: Option<&'static str>) -> &mut Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// The messages queued by the cortex (see `bank_send` and `wasm_execute`).
+    #[inline(always)]
+    pub fn take_messages(&mut self) -> Vec<CosmosMsg> {
+        std::mem::take(&mut *self.messages.borrow_mut())
+    }
+
+    /// Share compiled modules with other engines in this execution (i.e. other messages
+    /// or `cortex_call` chains), must be called before `load_core`.
+    #[inline(always)]
+    pub fn set_module_cache(&mut self, cache: Option<SharedModuleCache>) -> &mut Self {
+        self.module_cache = cache;
+        self
+    }
+
+    #[inline(always)]
+    pub fn module_cache_stats(&self) -> Option<ModuleCacheStats> {
+        self.module_cache.as_ref().map(|c| c.stats())
+    }
+
+    #[inline(always)]
+    pub fn call_depth(&self) -> u32 {
+        self.call_depth
+    }
+
+    /// Record the sender as admin of a newly deployed cortex, or ensure they
+    /// are the admin when upgrading an existing one.
+    pub fn authorize_deploy(&mut self, sender: &HumanAddr) -> Result<(), StdError> {
+        let name = self.cortex_name();
+        let mut deps = RefCell::borrow_mut(&*self.deps);
+        let deps = &mut *deps;
+
+        authorize_deploy(&mut deps.storage, &deps.api, &name, sender)
+    }
+
+    /// Paused cortexes reject handle (and cortex_call) but still serve queries.
+    pub fn ensure_not_paused(&self) -> Result<(), StdError> {
+        let name = self.cortex_name();
+        if is_paused(&RefCell::borrow(&*self.deps).storage, &name)? {
+            return Err(StdError::GenericErr {
+                msg: format!("cortex '{name}' is paused"),
+                backtrace: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Store the loaded core in the registry (under its cortex name) so it
+    /// may be invoked by other cortexes.
+    pub fn store_core(&mut self, bytes: &[u8]) -> Result<(), StdError> {
+        if self.cfg.is_none() {
+            return Err(StdError::GenericErr {
+                msg: format!("cannot call 'store_core' without a loaded config"),
+                backtrace: None,
+            });
+        }
+
+        let name = self.cortex_name();
+        let mut deps = RefCell::borrow_mut(&*self.deps);
+        store_bundle(&mut deps.storage, &name, bytes);
+        store_grants(&mut deps.storage, &name, &self.permissions)?;
+
+        Ok(())
+    }
+
     pub fn validate(&mut self) -> Result<(), StdError> {
         if !self.loaded_core() {
             return Err(StdError::GenericErr {
@@ -287,6 +610,38 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
             validate_endpoint_method(lib, endpoint_fname)?;
         }
 
+        self.validate_imports()
+    }
+
+    /// Statically check the imports reachable from every entrypoint, so missing modules
+    /// and cycles are caught at deploy rather than when a rarely used path runs.
+    pub fn validate_imports(&self) -> Result<(), StdError> {
+        let rc_resolver = RefCell::borrow(&self.rh_resolver);
+        let resolver = rc_resolver.as_ref().unwrap();
+
+        let graph = resolver.analyze_imports(&self.rh_engine)
+            .map_err(|err| {
+                return StdError::GenericErr {
+                    msg: format!("failed to analyze core imports: {err}"),
+                    backtrace: None,
+                };
+            })?;
+        if !graph.is_valid() {
+            return Err(StdError::GenericErr {
+                msg: format!("core has invalid imports: {}", graph.errors().join("; ")),
+                backtrace: None,
+            });
+        }
+
+        #[cfg(any(feature = "debug-print", feature = "test-print"))]
+        for file in &graph.unreachable {
+            #[cfg(feature = "debug-print")]
+            debug_print!("CORTEX[{}][warn ]: unreachable file '{}'", self.debug_label, file.display());
+
+            #[cfg(feature = "test-print")]
+            println!("CORTEX[{}][warn ]: unreachable file '{}'", self.debug_label, file.display());
+        }
+
         Ok(())
     }
 
@@ -320,6 +675,7 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
             self.rh_engine.call_fn_raw_raw(&mut scope, global, caches, &ast, false,
                                            true, "simple", None, &mut args, )
                 .map_err(|err| {
+                    let err = resolver.remap_entrypoint_error(*err);
                     return StdError::GenericErr {
                         msg: format!("failed to run 'handle' on rhai script: {err}"),
                         backtrace: None,
@@ -327,29 +683,144 @@ impl<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier> OmnibusEngine
                 })?;
         }
 
-        Ok(HandleResponse::default())
+        Ok(HandleResponse {
+            messages: std::mem::take(&mut *self.messages.borrow_mut()),
+            log: vec![],
+            data: None,
+        })
+    }
+
+    pub fn call_fn(&mut self, name: &str, mut args: Vec<Dynamic>) -> StdResult<Dynamic> {
+        if !self.loaded_core() {
+            return Err(StdError::GenericErr {
+                msg: format!("cannot call '{name}' without a compiled core"),
+                backtrace: None,
+            });
+        }
+
+        let rc_resolver = RefCell::borrow(&self.rh_resolver);
+        let resolver = rc_resolver.as_ref().unwrap();
+
+        let caches = self.rh_caches.as_mut().unwrap();
+        let global = self.rh_global.as_mut().unwrap();
+        let ast = self.rh_ast.as_ref().unwrap();
+        let mut scope = resolver.scope().clone();
+
+        self.rh_engine.call_fn_raw_raw(&mut scope, global, caches, ast, false,
+                                       true, name, None, &mut args)
+            .map_err(|err| {
+                let err = resolver.remap_entrypoint_error(*err);
+                return StdError::GenericErr {
+                    msg: format!("failed to run '{name}' on rhai script: {err}"),
+                    backtrace: None,
+                };
+            })
+    }
+
+    /// Invoke an exported function of another registered cortex in a fresh engine, with
+    /// the same package selection as the caller. Messages the callee queues are added to
+    /// `messages`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn call_cortex(
+        deps: Rc<RefCell<Extern<S, A, Q>>>,
+        env: Env,
+        caller: &str,
+        depth: u32,
+        cache: Option<SharedModuleCache>,
+        packages: &[PackageKind],
+        messages: Messages,
+        callee: &str,
+        fn_name: &str,
+        args: Vec<Dynamic>,
+    ) -> StdResult<Dynamic> {
+        if depth > MAX_CORTEX_CALL_DEPTH {
+            return Err(StdError::GenericErr {
+                msg: format!("cortex call depth limit ({MAX_CORTEX_CALL_DEPTH}) exceeded calling '{callee}'"),
+                backtrace: None,
+            });
+        }
+
+        let bytes = load_bundle(&RefCell::borrow(&*deps).storage, callee)?;
+
+        let mut engine = Self::new_with_packages(deps, packages)?;
+        engine.call_depth = depth;
+        engine.messages = messages;
+        engine.set_module_cache(cache);
+        engine.load_core(bytes, env)?;
+        engine.authorize_call(caller, fn_name)?;
+        engine.ensure_not_paused()?;
+        engine.call_fn(fn_name, args)
+    }
+
+    fn authorize_call(&self, caller: &str, fn_name: &str) -> Result<(), StdError> {
+        let cfg = self.cfg.as_ref().unwrap();
+
+        if !cfg.is_exported(fn_name) {
+            return Err(StdError::GenericErr {
+                msg: format!("cortex '{}' does not export '{fn_name}'", cfg.cortex_name()),
+                backtrace: None,
+            });
+        }
+        if !cfg.is_caller_allowed(caller) {
+            return Err(StdError::GenericErr {
+                msg: format!("cortex '{caller}' is not permitted to call '{}:{fn_name}'",
+                             cfg.cortex_name()),
+                backtrace: None,
+            });
+        }
+
+        Ok(())
     }
 }
 
 //// Utils
 
+/// A rhai engine as cores are compiled by (the packages given and strict variables, no
+/// host functions), i.e. to bake the consts of a bundle offline.
+pub fn new_compile_engine(packages: &[PackageKind]) -> Result<Engine, StdError> {
+    let mut selected = packages.to_vec();
+    if !selected.contains(&PackageKind::Core) {
+        selected.insert(0, PackageKind::Core);
+    }
+
+    let mut engine = Engine::new_raw();
+    engine.register_global_module(packages_module(&selected)?.into());
+    engine.set_strict_variables(true);
+
+    Ok(engine)
+}
+
+fn packages_module(packages: &[PackageKind]) -> Result<Module, StdError> {
+    let mut lib = Module::new();
+    for pkg in packages {
+        pkg.init(&mut lib)?;
+    }
+    lib.build_index();
+
+    Ok(lib)
+}
+
 // Keys
 // TODO: Move
-fn expand_key_path(key_path: &mut Vec<Dynamic>) -> Result<String, String> {
+fn expand_key_path(key_path: &mut Vec<Dynamic>) -> Result<Vec<u8>, String> {
     if key_path.is_empty() {
         return Err("key path is required.")?;
     }
 
-    let mut buf = String::new();
+    let mut buf: Vec<u8> = Vec::new();
     key_path.into_iter().try_for_each(|key| {
-        if !buf.is_empty() { buf.push_str("."); }
-
-        match key.read_lock::<ImmutableString>() {
-            None => {
-                return Err("keys must all be String.");
-            }
-            Some(v) => buf.push_str(v.as_str())
-        };
+        if !buf.is_empty() { buf.push(b'.'); }
+
+        if let Some(v) = key.read_lock::<ImmutableString>() {
+            // Same path syntax as config keys (i.e. `tiers[0]` is `tiers.0`).
+            let path = canonical_path(v.as_str()).map_err(|err| err.to_string())?;
+            buf.extend_from_slice(path.as_bytes());
+        } else if let Some(v) = key.read_lock::<Blob>() {
+            // i.e. canonical address bytes.
+            buf.extend_from_slice(v.as_slice());
+        } else {
+            return Err("keys must all be String or Blob.".to_string());
+        }
 
         Ok(())
     })?;
@@ -3,6 +3,10 @@ pub(crate) mod operations;
 pub(crate) mod rhai;
 pub(crate) mod cortex;
 
-pub use engine::OmnibusEngine;
-pub use operations::{deploy, handle};
-pub use cortex::config::CortexConfig;
\ No newline at end of file
+pub use engine::{new_compile_engine, OmnibusEngine};
+pub use operations::{admin, deploy, handle, handle_cortex, handle_cortex_with_cache, handle_with_cache,
+                     init, migrate_legacy_storage, transfer_contract_admin};
+pub use cortex::admin::AdminOp;
+pub use cortex::config::CortexConfig;
+pub use crate::rhai::packages::selection::PackageKind;
+pub use zip_module_resolver::{ModuleCacheStats, SharedModuleCache};
\ No newline at end of file
@@ -1,26 +1,556 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use cosmwasm_std::{Api, Env, Extern, HandleResponse, Querier, StdResult, Storage};
+use cosmwasm_std::{Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier, StdResult, Storage};
+use zip_module_resolver::SharedModuleCache;
+
 use crate::OmnibusEngine;
+use crate::engine::ENDPOINT_FN_HANDLE;
+use crate::cortex::admin::{AdminOp, apply_admin_op, ensure_contract_admin, init_contract_admin};
+use crate::cortex::registry::{load_bundle, migrate_legacy_keys};
+
+/// Record the sender as the contract admin (who approves cortex permissions).
+pub fn init<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+) -> StdResult<InitResponse> {
+    let mut deps = RefCell::borrow_mut(&*deps);
+    let deps = &mut *deps;
+
+    init_contract_admin(&mut deps.storage, &deps.api, &env.message.sender)?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn transfer_contract_admin<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    new_admin: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut deps = RefCell::borrow_mut(&*deps);
+    let deps = &mut *deps;
+
+    crate::cortex::admin::transfer_contract_admin(&mut deps.storage, &deps.api,
+                                                  &env.message.sender, &new_admin)?;
+
+    Ok(HandleResponse::default())
+}
+
+/// Move data written before cortex storage was namespaced into the storage of
+/// `cortex_name` (contract admin only). Cortexes can't read keys at the storage root,
+/// so contracts upgraded from a release without cortex namespaces must migrate them.
+pub fn migrate_legacy_storage<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    cortex_name: &str,
+    keys: Vec<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut deps = RefCell::borrow_mut(&*deps);
+    let deps = &mut *deps;
+
+    ensure_contract_admin(&deps.storage, &deps.api, &env.message.sender)?;
+    let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.0).collect();
+    migrate_legacy_keys(&mut deps.storage, cortex_name, &keys)?;
 
+    Ok(HandleResponse::default())
+}
+
+/// Deploy (or upgrade) a cortex, only the contract admin may approve permissions.
 pub fn deploy<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
     deps: Rc<RefCell<Extern<S, A, Q>>>,
     env: Env,
     data: Vec<u8>,
+    approved_permissions: Vec<String>,
 ) -> StdResult<HandleResponse> {
+    let sender = env.message.sender.clone();
+
+    if !approved_permissions.is_empty() {
+        let deps = RefCell::borrow(&*deps);
+        ensure_contract_admin(&deps.storage, &deps.api, &sender)?;
+    }
+
     let mut engine = OmnibusEngine::new(deps);
-    engine.load_core(data, env)?;
+    engine.approve_permissions(approved_permissions);
+    engine.load_core(data.clone(), env)?;
     engine.validate()?;
+    engine.authorize_deploy(&sender)?;
+    engine.store_core(&data)?;
     engine.run_deploy()
 }
 
+/// Run `handle` on the core `data`, which must be the bundle registered for its cortex.
 pub fn handle<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
     deps: Rc<RefCell<Extern<S, A, Q>>>,
     env: Env,
     data: Vec<u8>,
+) -> StdResult<HandleResponse> {
+    handle_with_cache(deps, env, data, SharedModuleCache::new())
+}
+
+/// As `handle`, sharing compiled modules with other messages in the same execution.
+pub fn handle_with_cache<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    data: Vec<u8>,
+    cache: SharedModuleCache,
 ) -> StdResult<HandleResponse> {
     let mut engine = OmnibusEngine::new(deps);
+    engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+    engine.set_module_cache(Some(cache));
     engine.load_core(data, env)?;
+    engine.ensure_not_paused()?;
     engine.run_handle()
-}
\ No newline at end of file
+}
+
+/// Run `handle` on the deployed cortex `cortex_name`.
+pub fn handle_cortex<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    cortex_name: &str,
+) -> StdResult<HandleResponse> {
+    handle_cortex_with_cache(deps, env, cortex_name, SharedModuleCache::new())
+}
+
+/// As `handle_cortex`, sharing compiled modules with other messages in the same execution.
+pub fn handle_cortex_with_cache<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    cortex_name: &str,
+    cache: SharedModuleCache,
+) -> StdResult<HandleResponse> {
+    let bytes = load_bundle(&RefCell::borrow(&*deps).storage, cortex_name)?;
+
+    handle_with_cache(deps, env, bytes, cache)
+}
+
+pub fn admin<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    env: Env,
+    cortex_name: &str,
+    op: AdminOp,
+) -> StdResult<HandleResponse> {
+    let mut deps = RefCell::borrow_mut(&*deps);
+    let deps = &mut *deps;
+
+    apply_admin_op(&mut deps.storage, &deps.api, cortex_name, &env.message.sender, op)?;
+
+    Ok(HandleResponse::default())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{BankMsg, coins, CosmosMsg, ReadonlyStorage, StdError, WasmMsg};
+    use rhai::{Dynamic, INT, Map};
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use crate::cortex::config::CortexConfig;
+    use crate::cortex::registry::cortex_storage_read;
+    #[cfg(feature = "pkg-math")]
+    use crate::rhai::packages::selection::PackageKind;
+
+    use super::*;
+
+    type MockDeps = Rc<RefCell<Extern<MockStorage, MockApi, MockQuerier>>>;
+
+    fn bundle(name: &str, script: &str) -> Vec<u8> {
+        bundle_with_config(&format!(r#"{{"cortex":{{"name":"{name}","version":"1.0.0"}},"global":{{"entrypoints":["main"]}}}}"#),
+                           script)
+    }
+
+    fn bundle_with_config(config: &str, script: &str) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buf);
+            zip.start_file("config.json", FileOptions::default()).unwrap();
+            zip.write_all(config.as_bytes()).unwrap();
+            zip.start_file("main.rhai", FileOptions::default()).unwrap();
+            zip.write_all(script.as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+
+        buf.into_inner()
+    }
+
+    fn setup() -> MockDeps {
+        let deps = Rc::new(RefCell::new(mock_dependencies(20, &[])));
+        init(deps.clone(), mock_env("owner", &[])).unwrap();
+        deps
+    }
+
+    fn assert_generic_err(err: StdError, expected: &str) {
+        match err {
+            StdError::GenericErr { msg, .. } => assert!(msg.contains(expected), "unexpected error: {msg}"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    fn load_config(deps: MockDeps, name: &str) -> CortexConfig {
+        let bytes = load_bundle(&RefCell::borrow(&*deps).storage, name).unwrap();
+
+        let mut engine = OmnibusEngine::new(deps);
+        engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+        engine.load_core(bytes, mock_env("anyone", &[])).unwrap();
+        engine.config().unwrap().clone()
+    }
+
+    fn call(deps: MockDeps, name: &str, fn_name: &str, args: Vec<Dynamic>) -> StdResult<Dynamic> {
+        let bytes = load_bundle(&RefCell::borrow(&*deps).storage, name).unwrap();
+
+        let mut engine = OmnibusEngine::new(deps);
+        engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+        engine.load_core(bytes, mock_env("anyone", &[])).unwrap();
+        engine.call_fn(fn_name, args)
+    }
+
+    fn assert_unauthorized(res: StdResult<HandleResponse>) {
+        match res.unwrap_err() {
+            StdError::Unauthorized { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    fn map_int(map: &Map, key: &str) -> INT {
+        map.get(key).unwrap().as_int().unwrap()
+    }
+
+    const SCRIPT: &'static str = "fn deploy() {} fn handle() {} fn query() {} fn simple() { 1 }";
+    const CHANGED_SCRIPT: &'static str = "fn deploy() {} fn handle() {} fn query() {} fn simple() { 2 }";
+
+    #[test]
+    fn changed_bundle_cannot_claim_paused_cortex() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", SCRIPT), vec![]).unwrap();
+        handle_cortex(deps.clone(), mock_env("anyone", &[]), "counter").unwrap();
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Pause).unwrap();
+        assert_generic_err(handle_cortex(deps.clone(), mock_env("anyone", &[]), "counter").unwrap_err(),
+                           "cortex 'counter' is paused");
+
+        // A different bundle declaring the same name is not the registered core.
+        let changed = bundle("counter", CHANGED_SCRIPT);
+        let mut engine = OmnibusEngine::new(deps.clone());
+        engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+        assert_generic_err(engine.load_core(changed.clone(), mock_env("anyone", &[])).unwrap_err(),
+                           "bundle is not the registered core for cortex 'counter'");
+
+        // Nor may anyone but the cortex admin deploy it over the paused cortex.
+        match deploy(deps.clone(), mock_env("mallory", &[]), changed.clone(), vec![]).unwrap_err() {
+            StdError::Unauthorized { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert_generic_err(handle_cortex(deps.clone(), mock_env("anyone", &[]), "counter").unwrap_err(),
+                           "cortex 'counter' is paused");
+
+        // An upgrade by the admin stays paused.
+        deploy(deps.clone(), mock_env("creator", &[]), changed, vec![]).unwrap();
+        assert_generic_err(handle_cortex(deps.clone(), mock_env("anyone", &[]), "counter").unwrap_err(),
+                           "cortex 'counter' is paused");
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Unpause).unwrap();
+        handle_cortex(deps, mock_env("anyone", &[]), "counter").unwrap();
+    }
+
+    #[test]
+    fn handle_runs_only_the_registered_bundle() {
+        let deps = setup();
+        let core = bundle("counter", SCRIPT);
+        deploy(deps.clone(), mock_env("creator", &[]), core.clone(), vec![]).unwrap();
+
+        handle(deps.clone(), mock_env("anyone", &[]), core).unwrap();
+        assert_generic_err(handle(deps, mock_env("anyone", &[]), bundle("counter", CHANGED_SCRIPT)).unwrap_err(),
+                           "bundle is not the registered core for cortex 'counter'");
+    }
+
+    #[test]
+    fn config_overrides_reject_readonly_keys() {
+        let deps = setup();
+        let config = r#"{"cortex":{"name":"fees","version":"1.0.0"},"global":{"entrypoints":["main"]},
+                         "config":{"readonly":["fees.rate"]},"fees":{"rate":1,"other":2}}"#;
+        deploy(deps.clone(), mock_env("creator", &[]), bundle_with_config(config, SCRIPT), vec![]).unwrap();
+
+        let set_overrides = |overrides: &str| {
+            admin(deps.clone(), mock_env("creator", &[]), "fees",
+                  AdminOp::SetConfigOverrides { overrides: overrides.to_string() })
+        };
+
+        set_overrides(r#"{"fees":{"other":3},"limits":{"max":10}}"#).unwrap();
+
+        assert_generic_err(set_overrides(r#"{"fees":{"rate":5}}"#).unwrap_err(),
+                           "config key 'fees.rate' is read-only");
+        assert_generic_err(set_overrides(r#"{"fees":5}"#).unwrap_err(),
+                           "config key 'fees' is read-only");
+        assert_generic_err(set_overrides(r#"{"cortex":{"name":"other"}}"#).unwrap_err(),
+                           "config key 'cortex.name' is read-only");
+        assert_generic_err(set_overrides(r#"{"permissions":[]}"#).unwrap_err(),
+                           "config key 'permissions' is read-only");
+        assert_generic_err(set_overrides("[]").unwrap_err(),
+                           "config overrides must be a json object");
+    }
+
+    #[test]
+    fn config_overrides_merge_into_bundle_maps() {
+        let deps = setup();
+        let config = r#"{"cortex":{"name":"fees","version":"1.0.0"},"global":{"entrypoints":["main"]},
+                         "config":{"readonly":["fees.rate"]},"fees":{"rate":1,"other":2},
+                         "limits":{"max":10,"min":1}}"#;
+        deploy(deps.clone(), mock_env("creator", &[]), bundle_with_config(config, SCRIPT), vec![]).unwrap();
+        admin(deps.clone(), mock_env("creator", &[]), "fees", AdminOp::SetConfigOverrides {
+            overrides: r#"{"fees":{"other":3},"limits":{"max":20}}"#.to_string(),
+        }).unwrap();
+
+        let cfg = load_config(deps, "fees");
+
+        // Reads of the parent map and of its keys agree.
+        assert_eq!(cfg.get_int("fees.other"), Some(3));
+        assert_eq!(cfg.get_int("fees.rate"), Some(1));
+        let fees = cfg.get("fees").cast::<Map>();
+        assert_eq!(map_int(&fees, "other"), 3);
+        assert_eq!(map_int(&fees, "rate"), 1);
+
+        assert_eq!(cfg.get_int("limits.max"), Some(20));
+        let limits = cfg.get("limits").cast::<Map>();
+        assert_eq!(map_int(&limits, "max"), 20);
+        assert_eq!(map_int(&limits, "min"), 1);
+    }
+
+    #[test]
+    fn granted_cortexes_queue_bank_and_wasm_messages() {
+        let deps = setup();
+        let script = r#"fn deploy() {} fn handle() {} fn query() {} fn simple() {}
+                        fn pay() {
+                            bank_send("alice", [#{denom: "uscrt", amount: 5}]);
+                            wasm_execute("token", "abcd", #{transfer: #{amount: "10"}}, []);
+                        }"#;
+        let granted = bundle_with_config(r#"{"cortex":{"name":"payer","version":"1.0.0"},"global":{"entrypoints":["main"]},
+                                             "permissions":["bank.send","wasm.execute"]}"#, script);
+        let ungranted = bundle("idle", script);
+        deploy(deps.clone(), mock_env("owner", &[]), granted.clone(),
+               vec!["bank.send".to_string(), "wasm.execute".to_string()]).unwrap();
+        deploy(deps.clone(), mock_env("owner", &[]), ungranted.clone(), vec![]).unwrap();
+
+        let mut engine = OmnibusEngine::new(deps.clone());
+        engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+        engine.load_core(granted, mock_env("anyone", &[])).unwrap();
+        engine.call_fn("pay", vec![]).unwrap();
+
+        let messages: Vec<CosmosMsg> = vec![
+            BankMsg::Send {
+                from_address: mock_env("anyone", &[]).contract.address,
+                to_address: HumanAddr::from("alice"),
+                amount: coins(5, "uscrt"),
+            }.into(),
+            WasmMsg::Execute {
+                contract_addr: HumanAddr::from("token"),
+                callback_code_hash: "abcd".to_string(),
+                msg: Binary(br#"{"transfer":{"amount":"10"}}"#.to_vec()),
+                send: vec![],
+            }.into(),
+        ];
+        assert_eq!(engine.take_messages(), messages);
+
+        // The host functions are only registered for cortexes granted the permissions.
+        let mut engine = OmnibusEngine::new(deps);
+        engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+        engine.load_core(ungranted, mock_env("anyone", &[])).unwrap();
+        assert_generic_err(engine.call_fn("pay", vec![]).unwrap_err(), "bank_send");
+        assert!(engine.take_messages().is_empty());
+    }
+
+    #[test]
+    fn migrated_legacy_keys_are_read_from_the_cortex_namespace() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", SCRIPT), vec![]).unwrap();
+        {
+            let mut deps = RefCell::borrow_mut(&*deps);
+            deps.storage.set(b"count", b"7");
+            deps.storage.set(b"owner", b"creator");
+        }
+        let keys = vec![Binary(b"count".to_vec()), Binary(b"owner".to_vec())];
+
+        // Only the contract admin may migrate.
+        match migrate_legacy_storage(deps.clone(), mock_env("creator", &[]), "counter", keys.clone()).unwrap_err() {
+            StdError::Unauthorized { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        migrate_legacy_storage(deps.clone(), mock_env("owner", &[]), "counter", keys.clone()).unwrap();
+        {
+            let deps = RefCell::borrow(&*deps);
+            let storage = &deps.storage;
+            let cortex = cortex_storage_read(storage, "counter");
+            assert_eq!(cortex.get(b"count"), Some(b"7".to_vec()));
+            assert_eq!(cortex.get(b"owner"), Some(b"creator".to_vec()));
+            assert_eq!(storage.get(b"count"), None);
+            assert_eq!(storage.get(b"owner"), None);
+        }
+
+        // Migrated keys are gone from the root, and are never overwritten.
+        match migrate_legacy_storage(deps.clone(), mock_env("owner", &[]), "counter", vec![Binary(b"missing".to_vec())]).unwrap_err() {
+            StdError::NotFound { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        RefCell::borrow_mut(&*deps).storage.set(b"count", b"8");
+        assert_generic_err(migrate_legacy_storage(deps.clone(), mock_env("owner", &[]), "counter", keys).unwrap_err(),
+                           "already exists in cortex 'counter'");
+        assert_eq!(cortex_storage_read(&RefCell::borrow(&*deps).storage, "counter").get(b"count"), Some(b"7".to_vec()));
+
+        // Namespaced keys (e.g. other cortexes' data) can't be moved.
+        assert_generic_err(migrate_legacy_storage(deps, mock_env("owner", &[]), "counter", vec![Binary(vec![0, 1, 2])]).unwrap_err(),
+                           "is namespaced");
+    }
+
+    const ADMIN_SCRIPT: &'static str = r#"fn deploy() {} fn handle() {} fn query() {} fn simple() {}
+                                          fn admin_is(addr) { is_admin(addr) }
+                                          fn role_is(addr, role) { has_role(addr, role) }"#;
+
+    #[test]
+    fn transfer_admin_hands_over_every_op() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", ADMIN_SCRIPT), vec![]).unwrap();
+        assert!(call(deps.clone(), "counter", "admin_is", vec!["creator".into()]).unwrap().as_bool().unwrap());
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter",
+              AdminOp::TransferAdmin { new_admin: HumanAddr::from("alice") }).unwrap();
+        assert!(call(deps.clone(), "counter", "admin_is", vec!["alice".into()]).unwrap().as_bool().unwrap());
+        assert!(!call(deps.clone(), "counter", "admin_is", vec!["creator".into()]).unwrap().as_bool().unwrap());
+
+        // The previous admin may no longer administer (or upgrade) the cortex.
+        assert_unauthorized(admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Pause));
+        assert_unauthorized(deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", SCRIPT), vec![]));
+
+        admin(deps.clone(), mock_env("alice", &[]), "counter", AdminOp::Pause).unwrap();
+        deploy(deps, mock_env("alice", &[]), bundle("counter", SCRIPT), vec![]).unwrap();
+    }
+
+    #[test]
+    fn pause_and_unpause_gate_handle() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", SCRIPT), vec![]).unwrap();
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Pause).unwrap();
+        // Pausing twice is harmless.
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Pause).unwrap();
+        assert_generic_err(handle_cortex(deps.clone(), mock_env("anyone", &[]), "counter").unwrap_err(),
+                           "cortex 'counter' is paused");
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::Unpause).unwrap();
+        handle_cortex(deps, mock_env("anyone", &[]), "counter").unwrap();
+    }
+
+    #[test]
+    fn roles_are_granted_and_revoked() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", ADMIN_SCRIPT), vec![]).unwrap();
+        let has_role = |addr: &str, role: &str| {
+            call(deps.clone(), "counter", "role_is", vec![addr.into(), role.into()]).unwrap().as_bool().unwrap()
+        };
+        assert!(!has_role("bob", "minter"));
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::GrantRole {
+            role: "minter".to_string(),
+            address: HumanAddr::from("bob"),
+        }).unwrap();
+        assert!(has_role("bob", "minter"));
+        assert!(!has_role("bob", "burner"));
+        assert!(!has_role("carol", "minter"));
+
+        admin(deps.clone(), mock_env("creator", &[]), "counter", AdminOp::RevokeRole {
+            role: "minter".to_string(),
+            address: HumanAddr::from("bob"),
+        }).unwrap();
+        assert!(!has_role("bob", "minter"));
+
+        // Revoking a role which was never granted is harmless.
+        admin(deps, mock_env("creator", &[]), "counter", AdminOp::RevokeRole {
+            role: "burner".to_string(),
+            address: HumanAddr::from("bob"),
+        }).unwrap();
+    }
+
+    #[test]
+    fn set_config_overrides_replaces_previous_overrides() {
+        let deps = setup();
+        let config = r#"{"cortex":{"name":"fees","version":"1.0.0"},"global":{"entrypoints":["main"]},
+                         "fees":{"rate":1,"other":2}}"#;
+        deploy(deps.clone(), mock_env("creator", &[]), bundle_with_config(config, SCRIPT), vec![]).unwrap();
+        let set_overrides = |overrides: &str| {
+            admin(deps.clone(), mock_env("creator", &[]), "fees",
+                  AdminOp::SetConfigOverrides { overrides: overrides.to_string() })
+        };
+
+        set_overrides(r#"{"fees":{"rate":5}}"#).unwrap();
+        let cfg = load_config(deps.clone(), "fees");
+        assert_eq!(cfg.get_int("fees.rate"), Some(5));
+        assert_eq!(cfg.get_int("fees.other"), Some(2));
+
+        set_overrides(r#"{"fees":{"other":7}}"#).unwrap();
+        let cfg = load_config(deps.clone(), "fees");
+        assert_eq!(cfg.get_int("fees.rate"), Some(1));
+        assert_eq!(cfg.get_int("fees.other"), Some(7));
+
+        assert!(set_overrides("{not json").is_err());
+        assert_eq!(load_config(deps, "fees").get_int("fees.other"), Some(7));
+    }
+
+    #[test]
+    fn admin_ops_reject_other_callers() {
+        let deps = setup();
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("counter", SCRIPT), vec![]).unwrap();
+
+        let ops = vec![
+            AdminOp::TransferAdmin { new_admin: HumanAddr::from("mallory") },
+            AdminOp::Pause,
+            AdminOp::Unpause,
+            AdminOp::SetConfigOverrides { overrides: "{}".to_string() },
+            AdminOp::GrantRole { role: "minter".to_string(), address: HumanAddr::from("mallory") },
+            AdminOp::RevokeRole { role: "minter".to_string(), address: HumanAddr::from("creator") },
+        ];
+        for op in ops {
+            // Neither a stranger nor the contract admin administers the cortex.
+            assert_unauthorized(admin(deps.clone(), mock_env("mallory", &[]), "counter", op.clone()));
+            assert_unauthorized(admin(deps.clone(), mock_env("owner", &[]), "counter", op));
+        }
+
+        // Nor may anyone administer a cortex which was never deployed.
+        assert_unauthorized(admin(deps.clone(), mock_env("creator", &[]), "missing", AdminOp::Pause));
+
+        handle_cortex(deps, mock_env("anyone", &[]), "counter").unwrap();
+    }
+
+    #[test]
+    fn required_packages_must_be_included() {
+        let deps = setup();
+        let bundle = |name: &str, packages: &str| {
+            bundle_with_config(&format!(r#"{{"cortex":{{"name":"{name}","version":"1.0.0"}},"global":{{"entrypoints":["main"]}},
+                                           "required_packages":{packages}}}"#), SCRIPT)
+        };
+
+        deploy(deps.clone(), mock_env("creator", &[]), bundle("plain", r#"["core"]"#), vec![]).unwrap();
+
+        // A package compiled out of this build.
+        #[cfg(not(feature = "pkg-crypto"))]
+        assert_generic_err(deploy(deps.clone(), mock_env("creator", &[]), bundle("hasher", r#"["core","crypto"]"#),
+                                  vec![]).unwrap_err(),
+                           "cortex requires rhai package 'crypto' which is not included");
+
+        // A package compiled in, but not selected for the engine.
+        #[cfg(feature = "pkg-math")]
+        {
+            let bytes = bundle("calc", r#"["math"]"#);
+            deploy(deps.clone(), mock_env("creator", &[]), bytes.clone(), vec![]).unwrap();
+
+            let mut engine = OmnibusEngine::new_with_packages(deps.clone(), &[PackageKind::Core]).unwrap();
+            engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+            assert_generic_err(engine.load_core(bytes.clone(), mock_env("anyone", &[])).unwrap_err(),
+                               "cortex requires rhai package 'math' which is not included");
+
+            let mut engine = OmnibusEngine::new_with_packages(deps, &[PackageKind::Math]).unwrap();
+            engine.set_endpoint(Some(ENDPOINT_FN_HANDLE));
+            engine.load_core(bytes, mock_env("anyone", &[])).unwrap();
+        }
+    }
+}
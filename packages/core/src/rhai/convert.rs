@@ -0,0 +1,75 @@
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Coin, Uint128};
+#[cfg(feature = "staking")]
+use cosmwasm_std::{FullDelegation, Validator};
+use rhai::{Array, Dynamic, INT, Map};
+
+use crate::rhai::json::JsonNumber;
+
+pub(crate) fn coin_to_map(coin: &Coin) -> Map {
+    let mut map = Map::new();
+    map.insert("denom".into(), Dynamic::from(coin.denom.clone()));
+    map.insert("amount".into(), Dynamic::from(coin.amount));
+    map
+}
+
+/// The inverse of `coin_to_map`, the amount may also be an int or a string of digits.
+pub(crate) fn map_to_coin(map: &Map) -> Result<Coin, String> {
+    let denom = match map.get("denom").and_then(|d| d.clone().into_string().ok()) {
+        Some(denom) if !denom.is_empty() => denom,
+        _ => return Err("coin 'denom' must be a non-empty string".to_string()),
+    };
+
+    let amount = map.get("amount").cloned().unwrap_or(Dynamic::UNIT);
+    let amount = if amount.is::<Uint128>() {
+        amount.cast::<Uint128>()
+    } else if amount.is::<JsonNumber>() {
+        amount.cast::<JsonNumber>().to_uint128()?
+    } else if amount.is::<INT>() {
+        let amount = amount.as_int().unwrap();
+        if amount < 0 {
+            return Err(format!("coin 'amount' cannot be negative: {amount}"));
+        }
+        Uint128(amount as u128)
+    } else if amount.is::<String>() {
+        Uint128::try_from(amount.into_string().unwrap().as_str())
+            .map_err(|err| err.to_string())?
+    } else {
+        return Err(format!("coin 'amount' must be a Uint128, int or string (found {})",
+                           amount.type_name()));
+    };
+
+    Ok(Coin { denom, amount })
+}
+
+/// An array of coin maps (see `map_to_coin`).
+pub(crate) fn array_to_coins(coins: Array) -> Result<Vec<Coin>, String> {
+    coins.into_iter()
+        .map(|coin| match coin.try_cast::<Map>() {
+            Some(map) => map_to_coin(&map),
+            None => Err("coins must be maps of 'denom' and 'amount'".to_string()),
+        })
+        .collect()
+}
+
+#[cfg(feature = "staking")]
+pub(crate) fn validator_to_map(validator: &Validator) -> Map {
+    let mut map = Map::new();
+    map.insert("address".into(), Dynamic::from(validator.address.to_string()));
+    map.insert("commission".into(), Dynamic::from(validator.commission.to_string()));
+    map.insert("max_commission".into(), Dynamic::from(validator.max_commission.to_string()));
+    map.insert("max_change_rate".into(), Dynamic::from(validator.max_change_rate.to_string()));
+    map
+}
+
+#[cfg(feature = "staking")]
+pub(crate) fn full_delegation_to_map(delegation: &FullDelegation) -> Map {
+    let mut map = Map::new();
+    map.insert("delegator".into(), Dynamic::from(delegation.delegator.to_string()));
+    map.insert("validator".into(), Dynamic::from(delegation.validator.to_string()));
+    map.insert("amount".into(), Dynamic::from_map(coin_to_map(&delegation.amount)));
+    map.insert("can_redelegate".into(), Dynamic::from_map(coin_to_map(&delegation.can_redelegate)));
+    map.insert("accumulated_rewards".into(), Dynamic::from_map(coin_to_map(&delegation.accumulated_rewards)));
+    map
+}
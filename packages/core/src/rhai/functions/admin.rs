@@ -0,0 +1,34 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cosmwasm_std::{Api, Extern, HumanAddr, Querier, Storage};
+use rhai::{Engine, EvalAltResult};
+
+use crate::cortex::admin::{has_role, is_admin};
+use crate::rhai::functions::map_std_err;
+
+pub(crate) fn register_admin_functions<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    engine: &mut Engine,
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+    name: String,
+) {
+    let d = deps.clone();
+    let ns = name.clone();
+    engine.register_result_fn("is_admin", move |addr: &str| -> Result<bool, Box<EvalAltResult>> {
+        let deps = RefCell::borrow(&*d);
+
+        is_admin(&deps.storage, &deps.api, &ns, &HumanAddr::from(addr))
+            .map_err(|err| map_std_err("is_admin", err))
+    });
+
+    let d = deps.clone();
+    let ns = name.clone();
+    engine.register_result_fn("has_role", move |addr: &str, role: &str| -> Result<bool, Box<EvalAltResult>> {
+        let deps = RefCell::borrow(&*d);
+        let addr = deps.api.canonical_address(&HumanAddr::from(addr))
+            .map_err(|err| map_std_err("has_role", err))?;
+
+        has_role(&deps.storage, &ns, role, &addr)
+            .map_err(|err| map_std_err("has_role", err))
+    });
+}
@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Querier, Storage};
+use rhai::{Blob, Engine, EvalAltResult};
+
+use crate::rhai::functions::map_std_err;
+
+pub(crate) fn register_api_functions<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    engine: &mut Engine,
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+) {
+    // Api is Copy and stateless, no need to hold on to deps.
+    let api = RefCell::borrow(&*deps).api;
+
+    engine.register_result_fn("addr_canonicalize", move |human: &str| -> Result<Blob, Box<EvalAltResult>> {
+        let canonical = api.canonical_address(&HumanAddr::from(human))
+            .map_err(|err| map_std_err("addr_canonicalize", err))?;
+
+        Ok(canonical.as_slice().to_vec())
+    });
+
+    engine.register_result_fn("addr_humanize", move |canonical: Blob| -> Result<String, Box<EvalAltResult>> {
+        let human = api.human_address(&CanonicalAddr::from(canonical))
+            .map_err(|err| map_std_err("addr_humanize", err))?;
+
+        Ok(human.0)
+    });
+
+    // Returns the normalized form of the address, raising an error if it is invalid.
+    engine.register_result_fn("addr_validate", move |human: &str| -> Result<String, Box<EvalAltResult>> {
+        let canonical = api.canonical_address(&HumanAddr::from(human))
+            .map_err(|err| map_std_err("addr_validate", err))?;
+        let normalized = api.human_address(&canonical)
+            .map_err(|err| map_std_err("addr_validate", err))?;
+
+        Ok(normalized.0)
+    });
+}
@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rhai::{Blob, Dynamic, Engine, EvalAltResult};
+use zip_module_resolver::ZipModuleResolver;
+
+use crate::rhai::json::from_json_str;
+
+pub(crate) fn register_asset_functions(
+    engine: &mut Engine,
+    resolver: Rc<RefCell<Option<ZipModuleResolver>>>,
+) {
+    let r = resolver.clone();
+    engine.register_result_fn("asset", move |path: &str| -> Result<Blob, Box<EvalAltResult>> {
+        read_asset(&r, "asset", path)
+    });
+
+    let r = resolver.clone();
+    engine.register_result_fn("asset_json", move |path: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let bytes = read_asset(&r, "asset_json", path)?;
+        let json = String::from_utf8(bytes)
+            .map_err(|err| format!("error during asset_json: {err}"))?;
+
+        Ok(from_json_str(&json)
+            .map_err(|err| format!("error during asset_json: {err}"))?)
+    });
+}
+
+fn read_asset(
+    resolver: &Rc<RefCell<Option<ZipModuleResolver>>>,
+    fn_name: &str,
+    path: &str,
+) -> Result<Blob, Box<EvalAltResult>> {
+    let rc_resolver = RefCell::borrow(&**resolver);
+    let resolver = rc_resolver.as_ref()
+        .ok_or_else(|| format!("error during {fn_name}: core not loaded"))?;
+
+    Ok(resolver.get_asset(path)
+        .map_err(|err| format!("error during {fn_name}: {err}"))?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    const CONFIG: &'static str = r#"{"cortex":{"name":"assets","version":"1.0.0"},"global":{"entrypoints":["main"]},
+                                     "assets":{"dir":"assets","max_size":8}}"#;
+
+    fn engine(files: &[(&str, &str)]) -> Engine {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buf);
+            for (name, content) in files {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let mut engine = Engine::new();
+        let mut resolver = ZipModuleResolver::new();
+        resolver.load_from_bytes(buf.into_inner()).unwrap();
+        resolver.init(&engine).unwrap();
+        register_asset_functions(&mut engine, Rc::new(RefCell::new(Some(resolver))));
+        engine
+    }
+
+    fn bundle_engine() -> Engine {
+        engine(&[
+            ("config.json", CONFIG),
+            ("main.rhai", "fn main() {}"),
+            ("assets/hello.txt", "hello"),
+            ("assets/data.json", r#"{"a":"b"}"#),
+            ("assets/big.txt", "123456789"),
+            ("secret.txt", "secret"),
+        ])
+    }
+
+    fn eval_err(engine: &Engine, script: &str) -> String {
+        engine.eval::<Dynamic>(script).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn asset_reads_files_in_the_assets_dir() {
+        let engine = bundle_engine();
+
+        assert_eq!(engine.eval::<Blob>(r#"asset("hello.txt")"#).unwrap(), b"hello".to_vec());
+        assert_eq!(engine.eval::<Blob>(r#"asset("/hello.txt")"#).unwrap(), b"hello".to_vec());
+        assert_eq!(engine.eval::<Blob>(r#"asset("sub/../hello.txt")"#).unwrap(), b"hello".to_vec());
+        assert_eq!(engine.eval::<String>(r#"asset_json("data.json").a"#).unwrap(), "b");
+    }
+
+    #[test]
+    fn missing_assets_raise() {
+        let engine = bundle_engine();
+
+        let err = eval_err(&engine, r#"asset("missing.txt")"#);
+        assert!(err.contains("error during asset: specified file not found"), "{err}");
+        let err = eval_err(&engine, r#"asset_json("missing.json")"#);
+        assert!(err.contains("error during asset_json: specified file not found"), "{err}");
+        let err = eval_err(&engine, r#"asset_json("hello.txt")"#);
+        assert!(err.contains("error during asset_json"), "{err}");
+    }
+
+    #[test]
+    fn assets_may_not_escape_the_assets_dir() {
+        let engine = bundle_engine();
+
+        for path in ["../secret.txt", "sub/../../secret.txt", "/../secret.txt", "", "."] {
+            let err = eval_err(&engine, &format!(r#"asset("{path}")"#));
+            assert!(err.contains("is outside of the assets directory"), "{path}: {err}");
+        }
+        let err = eval_err(&engine, r#"asset("../../secret.txt")"#);
+        assert!(err.contains("error during asset"), "{err}");
+    }
+
+    #[test]
+    fn assets_above_max_size_are_rejected() {
+        let engine = bundle_engine();
+
+        let err = eval_err(&engine, r#"asset("big.txt")"#);
+        assert!(err.contains("file 'assets/big.txt' exceeds the size limit of 8 bytes"), "{err}");
+    }
+
+    #[test]
+    fn assets_must_be_declared() {
+        let engine = engine(&[
+            ("config.json", r#"{"cortex":{"name":"assets","version":"1.0.0"},"global":{"entrypoints":["main"]}}"#),
+            ("main.rhai", "fn main() {}"),
+            ("assets/hello.txt", "hello"),
+        ]);
+
+        let err = eval_err(&engine, r#"asset("hello.txt")"#);
+        assert!(err.contains("no assets directory is declared in config"), "{err}");
+
+        let mut unloaded = Engine::new();
+        register_asset_functions(&mut unloaded, Rc::new(RefCell::new(None)));
+        let err = eval_err(&unloaded, r#"asset("hello.txt")"#);
+        assert!(err.contains("error during asset: core not loaded"), "{err}");
+    }
+}
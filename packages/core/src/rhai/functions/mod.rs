@@ -0,0 +1,14 @@
+use cosmwasm_std::StdError;
+use rhai::EvalAltResult;
+
+pub(crate) mod admin;
+pub(crate) mod api;
+pub(crate) mod assets;
+pub(crate) mod msgs;
+pub(crate) mod querier;
+
+/// Map a StdError from a host function into a Rhai exception.
+#[inline(always)]
+pub(crate) fn map_std_err(fn_name: &str, err: StdError) -> Box<EvalAltResult> {
+    format!("error during {fn_name}: {err}").into()
+}
@@ -0,0 +1,68 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cosmwasm_std::{BankMsg, Binary, CosmosMsg, Env, WasmMsg};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+
+use crate::rhai::convert::array_to_coins;
+use crate::rhai::json::to_json_string;
+
+/// Messages queued by a cortex (and the cortexes it calls), returned in the `HandleResponse`.
+pub(crate) type Messages = Rc<RefCell<Vec<CosmosMsg>>>;
+
+pub(crate) fn register_bank_functions(
+    engine: &mut Engine,
+    env: Option<Env>,
+    messages: Messages,
+) {
+    engine.register_result_fn("bank_send", move |to_address: &str, amount: Array| -> Result<(), Box<EvalAltResult>> {
+        let from_address = match env.as_ref() {
+            None => return Err("error during bank_send: no env available".into()),
+            Some(env) => env.contract.address.clone(),
+        };
+        let amount = array_to_coins(amount)
+            .map_err(|err| format!("error during bank_send: {err}"))?;
+
+        messages.borrow_mut().push(BankMsg::Send {
+            from_address,
+            to_address: to_address.into(),
+            amount,
+        }.into());
+
+        Ok(())
+    });
+}
+
+pub(crate) fn register_wasm_functions(
+    engine: &mut Engine,
+    messages: Messages,
+) {
+    let m = messages.clone();
+    engine.register_result_fn("wasm_execute", move |contract_addr: &str, callback_code_hash: &str, msg: &str, send: Array| -> Result<(), Box<EvalAltResult>> {
+        wasm_execute(&m, contract_addr, callback_code_hash, msg.as_bytes().to_vec(), send)
+    });
+
+    let m = messages.clone();
+    engine.register_result_fn("wasm_execute", move |contract_addr: &str, callback_code_hash: &str, msg: Map, send: Array| -> Result<(), Box<EvalAltResult>> {
+        let msg = to_json_string(&Dynamic::from_map(msg))
+            .map_err(|err| format!("error during wasm_execute: {err}"))?;
+
+        wasm_execute(&m, contract_addr, callback_code_hash, msg.into_bytes(), send)
+    });
+}
+
+/// Queue a `WasmMsg::Execute` of the json `msg` on another contract.
+fn wasm_execute(messages: &Messages, contract_addr: &str, callback_code_hash: &str,
+                msg: Vec<u8>, send: Array) -> Result<(), Box<EvalAltResult>> {
+    let send = array_to_coins(send)
+        .map_err(|err| format!("error during wasm_execute: {err}"))?;
+
+    messages.borrow_mut().push(WasmMsg::Execute {
+        contract_addr: contract_addr.into(),
+        callback_code_hash: callback_code_hash.to_string(),
+        msg: Binary(msg),
+        send,
+    }.into());
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cosmwasm_std::{Api, Binary, Empty, Extern, Querier, QueryRequest, StdError, StdResult, Storage, to_vec, WasmQuery};
+use rhai::{Array, Blob, Dynamic, Engine, EvalAltResult, Map};
+
+use crate::rhai::convert::coin_to_map;
+#[cfg(feature = "staking")]
+use crate::rhai::convert::{full_delegation_to_map, validator_to_map};
+use crate::rhai::functions::map_std_err;
+use crate::rhai::json::{from_json_str, to_json_string};
+
+pub(crate) fn register_querier_functions<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    engine: &mut Engine,
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+) {
+    let d = deps.clone();
+    engine.register_result_fn("query_balance", move |address: &str, denom: &str| -> Result<Map, Box<EvalAltResult>> {
+        let coin = RefCell::borrow(&*d).querier.query_balance(address, denom)
+            .map_err(|err| map_std_err("query_balance", err))?;
+
+        Ok(coin_to_map(&coin))
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_all_balances", move |address: &str| -> Result<Array, Box<EvalAltResult>> {
+        let coins = RefCell::borrow(&*d).querier.query_all_balances(address)
+            .map_err(|err| map_std_err("query_all_balances", err))?;
+
+        Ok(coins.iter().map(|c| Dynamic::from_map(coin_to_map(c))).collect())
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_smart", move |contract_addr: &str, callback_code_hash: &str, msg: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        smart_query(&RefCell::borrow(&*d).querier, contract_addr, callback_code_hash,
+                    msg.as_bytes().to_vec())
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_smart", move |contract_addr: &str, callback_code_hash: &str, msg: Map| -> Result<Dynamic, Box<EvalAltResult>> {
+        let msg = to_json_string(&Dynamic::from_map(msg))
+            .map_err(|err| format!("error during query_smart: {err}"))?;
+
+        smart_query(&RefCell::borrow(&*d).querier, contract_addr, callback_code_hash,
+                    msg.into_bytes())
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_raw", move |contract_addr: &str, callback_code_hash: &str, key: Blob| -> Result<Dynamic, Box<EvalAltResult>> {
+        raw_query(&RefCell::borrow(&*d).querier, contract_addr, callback_code_hash, key)
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_raw", move |contract_addr: &str, callback_code_hash: &str, key: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        raw_query(&RefCell::borrow(&*d).querier, contract_addr, callback_code_hash,
+                  key.as_bytes().to_vec())
+    });
+}
+
+#[cfg(feature = "staking")]
+pub(crate) fn register_staking_functions<S: 'static + Storage, A: 'static + Api, Q: 'static + Querier>(
+    engine: &mut Engine,
+    deps: Rc<RefCell<Extern<S, A, Q>>>,
+) {
+    let d = deps.clone();
+    engine.register_result_fn("query_validators", move || -> Result<Array, Box<EvalAltResult>> {
+        let validators = RefCell::borrow(&*d).querier.query_validators()
+            .map_err(|err| map_std_err("query_validators", err))?;
+
+        Ok(validators.iter().map(|v| Dynamic::from_map(validator_to_map(v))).collect())
+    });
+
+    let d = deps.clone();
+    engine.register_result_fn("query_delegation", move |delegator: &str, validator: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let delegation = RefCell::borrow(&*d).querier.query_delegation(delegator, validator)
+            .map_err(|err| map_std_err("query_delegation", err))?;
+
+        Ok(match delegation {
+            None => Dynamic::UNIT,
+            Some(delegation) => Dynamic::from_map(full_delegation_to_map(&delegation))
+        })
+    });
+}
+
+/// Returns the parsed json response of the remote contract.
+fn smart_query<Q: Querier>(querier: &Q, contract_addr: &str, callback_code_hash: &str,
+                           msg: Vec<u8>) -> Result<Dynamic, Box<EvalAltResult>> {
+    let res = wasm_query(querier, WasmQuery::Smart {
+        contract_addr: contract_addr.into(),
+        callback_code_hash: callback_code_hash.to_string(),
+        msg: Binary(msg),
+    }).map_err(|err| map_std_err("query_smart", err))?;
+
+    let json = String::from_utf8(res.0)
+        .map_err(|err| map_std_err("query_smart", StdError::invalid_utf8(err)))?;
+
+    from_json_str(&json)
+        .map_err(|err| format!("error during query_smart: {err}").into())
+}
+
+/// Returns the stored bytes or `()` if the key is not set on the remote contract.
+fn raw_query<Q: Querier>(querier: &Q, contract_addr: &str, callback_code_hash: &str,
+                         key: Vec<u8>) -> Result<Dynamic, Box<EvalAltResult>> {
+    let res = wasm_query(querier, WasmQuery::Raw {
+        contract_addr: contract_addr.into(),
+        key: Binary(key),
+        callback_code_hash: callback_code_hash.to_string(),
+    });
+
+    return match res {
+        Ok(bytes) => Ok(Dynamic::from_blob(bytes.0)),
+        Err(StdError::NotFound { .. }) => Ok(Dynamic::UNIT),
+        Err(err) => Err(map_std_err("query_raw", err))
+    };
+}
+
+/// Performs a wasm query returning the raw (unparsed) response.
+fn wasm_query<Q: Querier>(querier: &Q, query: WasmQuery) -> StdResult<Binary> {
+    let request: QueryRequest<Empty> = query.into();
+    let raw = to_vec(&request)?;
+
+    return match querier.raw_query(&raw) {
+        Err(sys) => Err(StdError::generic_err(format!("Querier system error: {}", sys))),
+        Ok(res) => res,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{Coin, coins, Uint128};
+    use cosmwasm_std::testing::{mock_dependencies, MOCK_CONTRACT_ADDR};
+
+    use super::*;
+
+    fn engine(balance: &[Coin]) -> Engine {
+        let mut engine = Engine::new();
+        register_querier_functions(&mut engine, Rc::new(RefCell::new(mock_dependencies(20, balance))));
+        engine
+    }
+
+    fn assert_coin(coin: &Map, denom: &str, amount: u128) {
+        assert_eq!(coin.len(), 2);
+        assert_eq!(coin["denom"].clone().into_string().unwrap(), denom);
+        assert!(coin["amount"].is::<Uint128>(), "amount is a {}", coin["amount"].type_name());
+        assert_eq!(coin["amount"].clone().cast::<Uint128>(), Uint128(amount));
+    }
+
+    #[test]
+    fn coin_to_map_works() {
+        assert_coin(&coin_to_map(&Coin::new(340282366920938463463374607431768211455, "uscrt")),
+                    "uscrt", u128::MAX);
+    }
+
+    #[test]
+    fn query_balance_works() {
+        let mut balance = coins(123, "uscrt");
+        balance.extend(coins(5, "ustake"));
+        let engine = engine(&balance);
+
+        let coin = engine.eval::<Map>(&format!(r#"query_balance("{MOCK_CONTRACT_ADDR}", "uscrt")"#)).unwrap();
+        assert_coin(&coin, "uscrt", 123);
+
+        // Unknown denoms and addresses have a zero balance.
+        let coin = engine.eval::<Map>(&format!(r#"query_balance("{MOCK_CONTRACT_ADDR}", "uatom")"#)).unwrap();
+        assert_coin(&coin, "uatom", 0);
+        let coin = engine.eval::<Map>(r#"query_balance("nobody", "uscrt")"#).unwrap();
+        assert_coin(&coin, "uscrt", 0);
+    }
+
+    #[test]
+    fn query_all_balances_works() {
+        let mut balance = coins(123, "uscrt");
+        balance.extend(coins(5, "ustake"));
+        let engine = engine(&balance);
+
+        let all = engine.eval::<Array>(&format!(r#"query_all_balances("{MOCK_CONTRACT_ADDR}")"#)).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_coin(&all[0].read_lock::<Map>().unwrap(), "uscrt", 123);
+        assert_coin(&all[1].read_lock::<Map>().unwrap(), "ustake", 5);
+
+        assert!(engine.eval::<Array>(r#"query_all_balances("nobody")"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_queries_raise_errors() {
+        let engine = engine(&[]);
+
+        let err = engine.eval::<Dynamic>(r#"query_smart("token", "abcd", #{balance: #{}})"#)
+            .unwrap_err().to_string();
+        assert!(err.contains("error during query_smart: Generic error: Querier system error"), "{err}");
+        assert!(err.contains("token"), "{err}");
+
+        let err = engine.eval::<Dynamic>(r#"query_raw("token", "abcd", "key")"#)
+            .unwrap_err().to_string();
+        assert!(err.contains("error during query_raw"), "{err}");
+
+        // The error can be caught by the script.
+        let caught = engine.eval::<bool>(r#"let caught = false;
+                                            try { query_smart("token", "abcd", "{}"); } catch { caught = true; }
+                                            caught"#)
+            .unwrap();
+        assert!(caught);
+    }
+}
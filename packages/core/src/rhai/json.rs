@@ -0,0 +1,441 @@
+//! Deterministic JSON conversion for Rhai values.
+//!
+//! Output is compact with map keys sorted (Rhai maps are ordered) and string escaping
+//! matching `serde-json-wasm`, so messages sent to other contracts are byte-stable.
+//! Integers outside the range of `INT` are kept as a `JsonNumber`.
+
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use rhai::{Array, Blob, Dynamic, ImmutableString, INT, Map};
+
+const MAX_DEPTH: usize = 64;
+
+/// A json integer too large for `INT` (i.e. a u64, i64 or u128 field), kept as its
+/// digits so it serializes back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonNumber(String);
+
+impl JsonNumber {
+    /// Any integer which fits in an i128 or u128 (without leading zeros).
+    pub fn parse(digits: &str) -> Result<Self, String> {
+        let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+        if unsigned.is_empty() || !unsigned.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(format!("invalid number '{digits}'"));
+        }
+        if unsigned.len() > 1 && unsigned.starts_with('0') {
+            return Err(format!("invalid number '{digits}' (leading zero)"));
+        }
+        if digits.parse::<i128>().is_err() && digits.parse::<u128>().is_err() {
+            return Err(format!("number '{digits}' out of range"));
+        }
+
+        Ok(Self(digits.to_string()))
+    }
+
+    #[inline(always)]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn to_uint128(&self) -> Result<Uint128, String> {
+        self.0.parse::<u128>()
+            .map(Uint128)
+            .map_err(|_| format!("number '{}' is not a Uint128", self.0))
+    }
+}
+
+pub(crate) fn to_json_string(value: &Dynamic) -> Result<String, String> {
+    let mut buf = String::new();
+    write_value(&mut buf, value, 0)?;
+
+    Ok(buf)
+}
+
+pub(crate) fn from_json_str(json: &str) -> Result<Dynamic, String> {
+    let mut parser = JsonParser { src: json.as_bytes(), pos: 0, depth: 0 };
+
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.src.len() {
+        return Err(format!("trailing characters at position {}", parser.pos));
+    }
+
+    Ok(value)
+}
+
+fn write_value(buf: &mut String, value: &Dynamic, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err(format!("value nested deeper than {MAX_DEPTH}"));
+    }
+
+    if value.is_unit() {
+        buf.push_str("null");
+    } else if let Ok(v) = value.as_bool() {
+        buf.push_str(if v { "true" } else { "false" });
+    } else if let Ok(v) = value.as_int() {
+        buf.push_str(&v.to_string());
+    } else if let Some(v) = value.read_lock::<JsonNumber>() {
+        buf.push_str(v.as_str());
+    } else if let Ok(v) = value.as_char() {
+        write_str(buf, &v.to_string());
+    } else if let Some(v) = value.read_lock::<ImmutableString>() {
+        write_str(buf, v.as_str());
+    } else if let Some(v) = value.read_lock::<Blob>() {
+        // Same representation as Binary.
+        write_str(buf, &Binary(v.to_vec()).to_base64());
+    } else if let Some(v) = value.read_lock::<Uint128>() {
+        write_str(buf, &v.to_string());
+    } else if let Some(v) = value.read_lock::<Decimal>() {
+        write_str(buf, &v.to_string());
+    } else if let Some(v) = value.read_lock::<Array>() {
+        buf.push('[');
+        for (i, item) in v.iter().enumerate() {
+            if i > 0 { buf.push(','); }
+            write_value(buf, item, depth + 1)?;
+        }
+        buf.push(']');
+    } else if let Some(v) = value.read_lock::<Map>() {
+        buf.push('{');
+        for (i, (key, item)) in v.iter().enumerate() {
+            if i > 0 { buf.push(','); }
+            write_str(buf, key.as_str());
+            buf.push(':');
+            write_value(buf, item, depth + 1)?;
+        }
+        buf.push('}');
+    } else {
+        return Err(format!("cannot convert '{}' to json", value.type_name()));
+    }
+
+    Ok(())
+}
+
+fn write_str(buf: &mut String, s: &str) {
+    const HEX: &'static [u8; 16] = b"0123456789abcdef";
+
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => buf.push_str("\\\\"),
+            '"' => buf.push_str("\\\""),
+            '\u{8}' => buf.push_str("\\b"),
+            '\u{c}' => buf.push_str("\\f"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => {
+                let b = c as u8;
+                buf.push_str("\\u00");
+                buf.push(HEX[(b >> 4) as usize] as char);
+                buf.push(HEX[(b & 0xf) as usize] as char);
+            }
+            _ => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+struct JsonParser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn parse_value(&mut self) -> Result<Dynamic, String> {
+        self.skip_whitespace();
+
+        return match self.peek() {
+            None => Err("unexpected end of json".to_string()),
+            Some(b'{') => self.nested(|p| p.parse_object()),
+            Some(b'[') => self.nested(|p| p.parse_array()),
+            Some(b'"') => Ok(Dynamic::from(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true", Dynamic::TRUE),
+            Some(b'f') => self.parse_literal("false", Dynamic::FALSE),
+            Some(b'n') => self.parse_literal("null", Dynamic::UNIT),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            Some(c) => Err(format!("unexpected character '{}' at position {}", c as char, self.pos)),
+        };
+    }
+
+    fn nested<F>(&mut self, f: F) -> Result<Dynamic, String>
+        where F: FnOnce(&mut Self) -> Result<Dynamic, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("json nested deeper than {MAX_DEPTH}"));
+        }
+        let res = f(self);
+        self.depth -= 1;
+
+        res
+    }
+
+    fn parse_object(&mut self) -> Result<Dynamic, String> {
+        self.expect(b'{')?;
+        let mut map = Map::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Dynamic::from_map(map));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+            map.insert(key.into(), value);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => break,
+                _ => return Err(format!("expected ',' or '}}' at position {}", self.pos)),
+            }
+        }
+
+        Ok(Dynamic::from_map(map))
+    }
+
+    fn parse_array(&mut self) -> Result<Dynamic, String> {
+        self.expect(b'[')?;
+        let mut array = Array::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Dynamic::from_array(array));
+        }
+
+        loop {
+            array.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b']') => break,
+                _ => return Err(format!("expected ',' or ']' at position {}", self.pos)),
+            }
+        }
+
+        Ok(Dynamic::from_array(array))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out: Vec<u8> = Vec::new();
+
+        loop {
+            match self.next() {
+                None => return Err("unterminated string".to_string()),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    match self.next() {
+                        Some(b'"') => out.push(b'"'),
+                        Some(b'\\') => out.push(b'\\'),
+                        Some(b'/') => out.push(b'/'),
+                        Some(b'b') => out.push(8),
+                        Some(b'f') => out.push(12),
+                        Some(b'n') => out.push(b'\n'),
+                        Some(b'r') => out.push(b'\r'),
+                        Some(b't') => out.push(b'\t'),
+                        Some(b'u') => {
+                            let c = self.parse_unicode_escape()?;
+                            let mut tmp = [0u8; 4];
+                            out.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                        }
+                        _ => return Err(format!("invalid escape at position {}", self.pos)),
+                    }
+                }
+                Some(c) if c < 0x20 => {
+                    return Err(format!("control character in string at position {}", self.pos));
+                }
+                Some(c) => out.push(c),
+            }
+        }
+
+        String::from_utf8(out).map_err(|err| err.to_string())
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let hi = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&hi) {
+            // Surrogate pair.
+            if self.next() != Some(b'\\') || self.next() != Some(b'u') {
+                return Err(format!("unpaired surrogate at position {}", self.pos));
+            }
+            let lo = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&lo) {
+                return Err(format!("invalid surrogate at position {}", self.pos));
+            }
+            0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+        } else {
+            hi
+        };
+
+        std::char::from_u32(code)
+            .ok_or_else(|| format!("invalid unicode escape at position {}", self.pos))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let mut code: u32 = 0;
+        for _ in 0..4 {
+            let digit = match self.next() {
+                Some(c @ b'0'..=b'9') => c - b'0',
+                Some(c @ b'a'..=b'f') => c - b'a' + 10,
+                Some(c @ b'A'..=b'F') => c - b'A' + 10,
+                _ => return Err(format!("invalid unicode escape at position {}", self.pos)),
+            };
+            code = (code << 4) | digit as u32;
+        }
+
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Dynamic, String> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        if let Some(b'.') | Some(b'e') | Some(b'E') = self.peek() {
+            return Err(format!("fractional numbers are not supported (position {start})"));
+        }
+
+        let digits = std::str::from_utf8(&self.src[start..self.pos])
+            .map_err(|err| err.to_string())?;
+        let number = JsonNumber::parse(digits)
+            .map_err(|err| format!("{err} at position {start}"))?;
+
+        return match digits.parse::<INT>() {
+            Ok(value) => Ok(Dynamic::from_int(value)),
+            Err(_) => Ok(Dynamic::from(number)),
+        };
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Dynamic) -> Result<Dynamic, String> {
+        if self.src[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(format!("unexpected token at position {}", self.pos))
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.next() != Some(c) {
+            return Err(format!("expected '{}' at position {}", c as char, self.pos));
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{from_slice, to_vec};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Nested {
+        ok: bool,
+        values: Vec<i32>,
+    }
+
+    // Fields are in key order, as rhai maps are.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Msg {
+        amount: Uint128,
+        big: u64,
+        count: u32,
+        memo: String,
+        negative: i64,
+        nested: Nested,
+        payload: Binary,
+        rate: Decimal,
+        unset: Option<u32>,
+    }
+
+    fn msg() -> Msg {
+        Msg {
+            amount: Uint128(340282366920938463463374607431768211455),
+            big: u64::MAX,
+            count: 7,
+            memo: "quote \" slash \\ line\n tab\t ü".to_string(),
+            negative: -1_099_511_627_776,
+            nested: Nested { ok: true, values: vec![-1, 0, i32::MAX] },
+            payload: Binary(b"omnibus".to_vec()),
+            rate: Decimal::percent(150),
+            unset: None,
+        }
+    }
+
+    #[test]
+    fn round_trips_serde_json_wasm_output() {
+        let bytes = to_vec(&msg()).unwrap();
+
+        let value = from_json_str(std::str::from_utf8(&bytes).unwrap()).unwrap();
+        let json = to_json_string(&value).unwrap();
+
+        assert_eq!(json.as_bytes(), bytes.as_slice());
+        assert_eq!(from_slice::<Msg>(json.as_bytes()).unwrap(), msg());
+    }
+
+    #[test]
+    fn keeps_numbers_outside_int() {
+        let json = "[2147483647,2147483648,18446744073709551615,-1099511627776]";
+        let value = from_json_str(json).unwrap();
+        let array = value.clone().cast::<Array>();
+
+        assert_eq!(array[0].as_int().unwrap(), INT::MAX);
+        assert_eq!(array[1].clone().cast::<JsonNumber>().as_str(), "2147483648");
+        assert_eq!(array[2].clone().cast::<JsonNumber>().to_uint128().unwrap(),
+                   Uint128(u64::MAX as u128));
+        assert!(array[3].clone().cast::<JsonNumber>().to_uint128().is_err());
+
+        assert_eq!(to_json_string(&value).unwrap(), json);
+        assert_eq!(from_slice::<(i32, u64, u64, i64)>(json.as_bytes()).unwrap(),
+                   (i32::MAX, 2147483648, u64::MAX, -1099511627776));
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        assert!(from_json_str("1.5").is_err());
+        assert!(from_json_str("1e3").is_err());
+        assert!(from_json_str("01").is_err());
+        assert!(from_json_str("-").is_err());
+        assert!(from_json_str("340282366920938463463374607431768211456").is_err());
+    }
+
+    #[test]
+    fn sorts_map_keys_as_serde_json_wasm() {
+        let value = from_json_str(r#"{"b":1,"a":{"d":null,"c":"x"}}"#).unwrap();
+
+        assert_eq!(to_json_string(&value).unwrap(), r#"{"a":{"c":"x","d":null},"b":1}"#);
+    }
+}
@@ -1 +1,4 @@
+pub(crate) mod convert;
+pub(crate) mod functions;
+pub(crate) mod json;
 pub(crate) mod packages;
\ No newline at end of file
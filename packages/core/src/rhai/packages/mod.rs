@@ -1 +1,8 @@
-pub(crate) mod pkg_std;
\ No newline at end of file
+#[cfg(feature = "pkg-crypto")]
+pub(crate) mod pkg_crypto;
+#[cfg(feature = "pkg-math128")]
+pub(crate) mod pkg_math128;
+#[cfg(feature = "pkg-json")]
+pub(crate) mod pkg_json;
+pub(crate) mod pkg_std;
+pub(crate) mod selection;
\ No newline at end of file
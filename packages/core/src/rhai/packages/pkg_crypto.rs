@@ -0,0 +1,205 @@
+use std::convert::TryFrom;
+use std::prelude::v1::*;
+
+use cosmwasm_std::Binary;
+use ed25519_zebra::{Signature as Ed25519Signature, VerificationKey};
+use k256::ecdsa::{Signature as Secp256k1Signature, VerifyingKey};
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use rhai::{Blob, def_package, EvalAltResult};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+pub const MESSAGE_HASH_LEN: usize = 32;
+pub const ECDSA_SIGNATURE_LEN: usize = 64;
+pub const EDDSA_SIGNATURE_LEN: usize = 64;
+pub const EDDSA_PUBKEY_LEN: usize = 32;
+
+def_package! {
+    /// Package providing hashing, signature verification and encoding primitives.
+    ///
+    /// All implementations are pure rust (suitable for wasm32).
+    pub CryptoPackage(lib) {
+        init_hashing(lib);
+        init_signatures(lib);
+        init_encoding(lib);
+    }
+}
+
+fn init_hashing(lib: &mut rhai::Module) {
+    lib.set_native_fn("sha256", |data: Blob| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Sha256::digest(&data).to_vec())
+    });
+    lib.set_native_fn("sha256", |data: &str| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Sha256::digest(data.as_bytes()).to_vec())
+    });
+    lib.set_native_fn("keccak256", |data: Blob| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Keccak256::digest(&data).to_vec())
+    });
+    lib.set_native_fn("keccak256", |data: &str| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Keccak256::digest(data.as_bytes()).to_vec())
+    });
+    lib.set_native_fn("ripemd160", |data: Blob| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Ripemd160::digest(&data).to_vec())
+    });
+    lib.set_native_fn("ripemd160", |data: &str| -> Result<Blob, Box<EvalAltResult>> {
+        Ok(Ripemd160::digest(data.as_bytes()).to_vec())
+    });
+}
+
+fn init_signatures(lib: &mut rhai::Module) {
+    // Verifies a (low-s, r || s) signature over a 32 byte message hash with a
+    // compressed (33 byte) or uncompressed (65 byte) public key.
+    lib.set_native_fn("secp256k1_verify", |message_hash: Blob, signature: Blob, public_key: Blob| -> Result<bool, Box<EvalAltResult>> {
+        if message_hash.len() != MESSAGE_HASH_LEN {
+            return Err(format!("secp256k1_verify: message hash must be {MESSAGE_HASH_LEN} bytes").into());
+        }
+        if signature.len() != ECDSA_SIGNATURE_LEN {
+            return Err(format!("secp256k1_verify: signature must be {ECDSA_SIGNATURE_LEN} bytes").into());
+        }
+
+        let signature = Secp256k1Signature::from_slice(&signature)
+            .map_err(|err| format!("secp256k1_verify: invalid signature: {err}"))?;
+        let public_key = VerifyingKey::from_sec1_bytes(&public_key)
+            .map_err(|err| format!("secp256k1_verify: invalid public key: {err}"))?;
+
+        Ok(public_key.verify_prehash(&message_hash, &signature).is_ok())
+    });
+
+    lib.set_native_fn("ed25519_verify", |message: Blob, signature: Blob, public_key: Blob| -> Result<bool, Box<EvalAltResult>> {
+        let signature = <[u8; EDDSA_SIGNATURE_LEN]>::try_from(signature.as_slice())
+            .map_err(|_| format!("ed25519_verify: signature must be {EDDSA_SIGNATURE_LEN} bytes"))?;
+        let public_key = <[u8; EDDSA_PUBKEY_LEN]>::try_from(public_key.as_slice())
+            .map_err(|_| format!("ed25519_verify: public key must be {EDDSA_PUBKEY_LEN} bytes"))?;
+
+        return match VerificationKey::try_from(public_key) {
+            Ok(key) => Ok(key.verify(&Ed25519Signature::from(signature), &message).is_ok()),
+            Err(err) => Err(format!("ed25519_verify: invalid public key: {err}").into())
+        };
+    });
+}
+
+fn init_encoding(lib: &mut rhai::Module) {
+    lib.set_native_fn("hex_encode", |data: Blob| -> Result<String, Box<EvalAltResult>> {
+        Ok(hex::encode(data))
+    });
+    lib.set_native_fn("hex_decode", |data: &str| -> Result<Blob, Box<EvalAltResult>> {
+        hex::decode(data)
+            .map_err(|err| format!("hex_decode: {err}").into())
+    });
+    lib.set_native_fn("base64_encode", |data: Blob| -> Result<String, Box<EvalAltResult>> {
+        Ok(Binary(data).to_base64())
+    });
+    lib.set_native_fn("base64_decode", |data: &str| -> Result<Blob, Box<EvalAltResult>> {
+        Binary::from_base64(data)
+            .map(|b| b.0)
+            .map_err(|err| format!("base64_decode: {err}").into())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use rhai::{Dynamic, Engine};
+    use rhai::packages::Package;
+
+    use super::*;
+
+    // secp256k1 (low-s) signature of sha256("cortex")
+    const SECP256K1_SIG: &str = "ec62e2441996650823286310937db25f3e9a0b1ed2af1b430ba8e5ff203288877783909d2f1a85ff59beb6a81b7d9c07240b4e4e1a3705c805a8456756bc5687";
+    const SECP256K1_PUBKEY: &str = "02bb50e2d89a4ed70663d080659fe0ad4b9bc3e06c17a227433966cb59ceee020d";
+    // ed25519 signature of "cortex" with the RFC 8032 test 1 key
+    const ED25519_SIG: &str = "0b61783d01ca34b63e9f76b864c4b4ca96eef95435c63c85a7fb291b0f082655b9bd4884d15835d4e8e0fffe151634d5e9c68eba0aff7ee9eb84c0c7f65eb70d";
+    const ED25519_PUBKEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.register_global_module(CryptoPackage::new().as_shared_module());
+        engine
+    }
+
+    fn eval_hex(script: &str) -> String {
+        engine().eval::<String>(&format!("hex_encode({script})")).unwrap()
+    }
+
+    fn eval_bool(script: &str) -> bool {
+        engine().eval::<bool>(script).unwrap()
+    }
+
+    fn eval_err(script: &str) -> String {
+        engine().eval::<Dynamic>(script).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn hashes_match_known_answers() {
+        assert_eq!(eval_hex(r#"sha256("abc")"#), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(eval_hex(r#"sha256(hex_decode("000102ff"))"#), "3d1f57c984978ef98a18378c8166c1cb8ede02c03eeb6aee7e2f121dfeee3e56");
+
+        assert_eq!(eval_hex(r#"keccak256("")"#), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(eval_hex(r#"keccak256("abc")"#), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+        assert_eq!(eval_hex(r#"keccak256(hex_decode("000102ff"))"#), "f746f73a429e97e187d63bdf24be339478da1a3bdde9565e15d5ad9462ddee82");
+
+        assert_eq!(eval_hex(r#"ripemd160("abc")"#), "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+        assert_eq!(eval_hex(r#"ripemd160(hex_decode("000102ff"))"#), "ea081363ff1934d0243cc3bdfe51ff9678dc0723");
+    }
+
+    #[test]
+    fn secp256k1_verify_works() {
+        let verify = |hash: &str, sig: &str| {
+            eval_bool(&format!(r#"secp256k1_verify({hash}, hex_decode("{sig}"), hex_decode("{SECP256K1_PUBKEY}"))"#))
+        };
+
+        assert!(verify(r#"sha256("cortex")"#, SECP256K1_SIG));
+        assert!(!verify(r#"sha256("cortex!")"#, SECP256K1_SIG));
+
+        let mut tampered = SECP256K1_SIG.to_string();
+        tampered.replace_range(..2, "ed");
+        assert!(!verify(r#"sha256("cortex")"#, &tampered));
+    }
+
+    #[test]
+    fn secp256k1_verify_rejects_bad_input() {
+        let err = eval_err(&format!(r#"secp256k1_verify(hex_decode("00"), hex_decode("{SECP256K1_SIG}"), hex_decode("{SECP256K1_PUBKEY}"))"#));
+        assert!(err.contains("message hash must be 32 bytes"), "{err}");
+
+        let err = eval_err(&format!(r#"secp256k1_verify(sha256("cortex"), hex_decode("00"), hex_decode("{SECP256K1_PUBKEY}"))"#));
+        assert!(err.contains("signature must be 64 bytes"), "{err}");
+
+        let err = eval_err(&format!(r#"secp256k1_verify(sha256("cortex"), hex_decode("{SECP256K1_SIG}"), hex_decode("0102"))"#));
+        assert!(err.contains("invalid public key"), "{err}");
+    }
+
+    #[test]
+    fn ed25519_verify_works() {
+        let verify = |msg: &str, sig: &str| {
+            eval_bool(&format!(r#"ed25519_verify(hex_decode("{msg}"), hex_decode("{sig}"), hex_decode("{ED25519_PUBKEY}"))"#))
+        };
+
+        // "cortex"
+        assert!(verify("636f72746578", ED25519_SIG));
+        assert!(!verify("636f72746579", ED25519_SIG));
+
+        let mut tampered = ED25519_SIG.to_string();
+        tampered.replace_range(..2, "0c");
+        assert!(!verify("636f72746578", &tampered));
+
+        let err = eval_err(&format!(r#"ed25519_verify(hex_decode("00"), hex_decode("00"), hex_decode("{ED25519_PUBKEY}"))"#));
+        assert!(err.contains("signature must be 64 bytes"), "{err}");
+        let err = eval_err(&format!(r#"ed25519_verify(hex_decode("00"), hex_decode("{ED25519_SIG}"), hex_decode("00"))"#));
+        assert!(err.contains("public key must be 32 bytes"), "{err}");
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        assert_eq!(engine().eval::<String>(r#"hex_encode(hex_decode("00ff10ab"))"#).unwrap(), "00ff10ab");
+        assert_eq!(engine().eval::<String>(r#"base64_encode(hex_decode("00ff10ab"))"#).unwrap(), "AP8Qqw==");
+        assert_eq!(eval_hex(r#"base64_decode("AP8Qqw==")"#), "00ff10ab");
+        assert_eq!(engine().eval::<String>(r#"base64_encode(base64_decode("b21uaWJ1cw=="))"#).unwrap(), "b21uaWJ1cw==");
+    }
+
+    #[test]
+    fn encoding_rejects_invalid_input() {
+        assert!(eval_err(r#"hex_decode("abc")"#).contains("hex_decode"));
+        assert!(eval_err(r#"hex_decode("zz")"#).contains("hex_decode"));
+        assert!(eval_err(r#"base64_decode("not base64!")"#).contains("base64_decode"));
+    }
+}
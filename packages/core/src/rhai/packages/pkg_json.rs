@@ -0,0 +1,49 @@
+use std::prelude::v1::*;
+
+use cosmwasm_std::{Binary, Uint128};
+use rhai::{def_package, Dynamic, EvalAltResult};
+
+use crate::rhai::json::{from_json_str, JsonNumber, to_json_string};
+
+def_package! {
+    /// Package providing deterministic JSON and `Binary` (base64 JSON) serialization,
+    /// consistent with `cosmwasm_std::to_binary` / `from_binary`.
+    pub JsonPackage(lib) {
+        // Integers parsed from json which don't fit in `INT`.
+        lib.set_custom_type::<JsonNumber>("JsonNumber");
+        lib.set_native_fn("json_number", |digits: &str| -> Result<JsonNumber, Box<EvalAltResult>> {
+            JsonNumber::parse(digits)
+                .map_err(|err| format!("json_number: {err}").into())
+        });
+        lib.set_native_fn("to_string", |n: &mut JsonNumber| -> Result<String, Box<EvalAltResult>> {
+            Ok(n.as_str().to_string())
+        });
+        lib.set_native_fn("to_uint128", |n: &mut JsonNumber| -> Result<Uint128, Box<EvalAltResult>> {
+            n.to_uint128()
+                .map_err(|err| format!("to_uint128: {err}").into())
+        });
+        lib.set_native_fn("to_json", |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+            to_json_string(&value)
+                .map_err(|err| format!("to_json: {err}").into())
+        });
+        lib.set_native_fn("from_json", |json: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            from_json_str(json)
+                .map_err(|err| format!("from_json: {err}").into())
+        });
+        lib.set_native_fn("to_binary", |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+            let json = to_json_string(&value)
+                .map_err(|err| format!("to_binary: {err}"))?;
+
+            Ok(Binary(json.into_bytes()).to_base64())
+        });
+        lib.set_native_fn("from_binary", |data: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let bytes = Binary::from_base64(data)
+                .map_err(|err| format!("from_binary: {err}"))?;
+            let json = String::from_utf8(bytes.0)
+                .map_err(|err| format!("from_binary: {err}"))?;
+
+            from_json_str(&json)
+                .map_err(|err| format!("from_binary: {err}").into())
+        });
+    }
+}
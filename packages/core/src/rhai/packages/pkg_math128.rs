@@ -0,0 +1,199 @@
+use std::convert::TryFrom;
+use std::prelude::v1::*;
+use std::str::FromStr;
+
+use cosmwasm_std::{Decimal, StdError, Uint128};
+use rhai::{def_package, EvalAltResult, INT};
+
+def_package! {
+    /// Package providing 128-bit `Uint128` and fixed-point `Decimal` (matching
+    /// `cosmwasm_std::math`) so token amounts are not limited by `only_i32`.
+    ///
+    /// All arithmetic is checked, overflow and underflow raise an error.
+    pub Math128Package(lib) {
+        lib.set_custom_type::<Uint128>("Uint128");
+        lib.set_custom_type::<Decimal>("Decimal");
+
+        init_uint128(lib);
+        init_decimal(lib);
+    }
+}
+
+fn init_uint128(lib: &mut rhai::Module) {
+    lib.set_native_fn("uint128", |val: &str| -> Result<Uint128, Box<EvalAltResult>> {
+        Uint128::try_from(val).map_err(map_std_err)
+    });
+    lib.set_native_fn("uint128", |val: INT| -> Result<Uint128, Box<EvalAltResult>> {
+        if val < 0 {
+            return Err(format!("Uint128 cannot be negative: {val}").into());
+        }
+        Ok(Uint128(val as u128))
+    });
+    lib.set_native_fn("to_int", |val: &mut Uint128| -> Result<INT, Box<EvalAltResult>> {
+        INT::try_from(val.u128())
+            .map_err(|_| format!("Uint128 too large for int: {val}").into())
+    });
+    lib.set_native_fn("is_zero", |val: &mut Uint128| -> Result<bool, Box<EvalAltResult>> {
+        Ok(val.is_zero())
+    });
+    lib.set_native_fn("to_string", |val: &mut Uint128| -> Result<String, Box<EvalAltResult>> {
+        Ok(val.to_string())
+    });
+    lib.set_native_fn("to_debug", |val: &mut Uint128| -> Result<String, Box<EvalAltResult>> {
+        Ok(format!("Uint128({val})"))
+    });
+
+    lib.set_native_fn("+", |a: Uint128, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_add(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("-", |a: Uint128, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_sub(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("*", |a: Uint128, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_mul(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("/", |a: Uint128, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_div(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("%", |a: Uint128, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_rem(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("*", |a: Uint128, b: Decimal| -> Result<Uint128, Box<EvalAltResult>> {
+        b.checked_mul_uint128(a).map_err(map_std_err)
+    });
+    lib.set_native_fn("multiply_ratio", |val: &mut Uint128, nom: Uint128, denom: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        val.checked_multiply_ratio(nom, denom).map_err(map_std_err)
+    });
+
+    lib.set_native_fn("==", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a == b) });
+    lib.set_native_fn("!=", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a != b) });
+    lib.set_native_fn("<", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a < b) });
+    lib.set_native_fn("<=", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a <= b) });
+    lib.set_native_fn(">", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a > b) });
+    lib.set_native_fn(">=", |a: Uint128, b: Uint128| -> Result<bool, Box<EvalAltResult>> { Ok(a >= b) });
+}
+
+fn init_decimal(lib: &mut rhai::Module) {
+    lib.set_native_fn("decimal", |val: &str| -> Result<Decimal, Box<EvalAltResult>> {
+        Decimal::from_str(val).map_err(map_std_err)
+    });
+    lib.set_native_fn("decimal_percent", |val: INT| -> Result<Decimal, Box<EvalAltResult>> {
+        if val < 0 {
+            return Err(format!("Decimal cannot be negative: {val}").into());
+        }
+        Ok(Decimal::percent(val as u64))
+    });
+    lib.set_native_fn("decimal_permille", |val: INT| -> Result<Decimal, Box<EvalAltResult>> {
+        if val < 0 {
+            return Err(format!("Decimal cannot be negative: {val}").into());
+        }
+        Ok(Decimal::permille(val as u64))
+    });
+    lib.set_native_fn("decimal_from_ratio", |nom: Uint128, denom: Uint128| -> Result<Decimal, Box<EvalAltResult>> {
+        Decimal::checked_from_ratio(nom, denom).map_err(map_std_err)
+    });
+    lib.set_native_fn("is_zero", |val: &mut Decimal| -> Result<bool, Box<EvalAltResult>> {
+        Ok(val.is_zero())
+    });
+    lib.set_native_fn("to_string", |val: &mut Decimal| -> Result<String, Box<EvalAltResult>> {
+        Ok(val.to_string())
+    });
+    lib.set_native_fn("to_debug", |val: &mut Decimal| -> Result<String, Box<EvalAltResult>> {
+        Ok(format!("Decimal({val})"))
+    });
+
+    lib.set_native_fn("+", |a: Decimal, b: Decimal| -> Result<Decimal, Box<EvalAltResult>> {
+        a.checked_add(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("-", |a: Decimal, b: Decimal| -> Result<Decimal, Box<EvalAltResult>> {
+        a.checked_sub(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("*", |a: Decimal, b: Decimal| -> Result<Decimal, Box<EvalAltResult>> {
+        a.checked_mul(b).map_err(map_std_err)
+    });
+    lib.set_native_fn("*", |a: Decimal, b: Uint128| -> Result<Uint128, Box<EvalAltResult>> {
+        a.checked_mul_uint128(b).map_err(map_std_err)
+    });
+
+    lib.set_native_fn("==", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a == b) });
+    lib.set_native_fn("!=", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a != b) });
+    lib.set_native_fn("<", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a < b) });
+    lib.set_native_fn("<=", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a <= b) });
+    lib.set_native_fn(">", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a > b) });
+    lib.set_native_fn(">=", |a: Decimal, b: Decimal| -> Result<bool, Box<EvalAltResult>> { Ok(a >= b) });
+}
+
+#[inline(always)]
+fn map_std_err(err: StdError) -> Box<EvalAltResult> {
+    err.to_string().into()
+}
+
+#[cfg(test)]
+mod test {
+    use rhai::Engine;
+    use rhai::packages::Package;
+
+    use super::*;
+
+    const MAX: &str = "340282366920938463463374607431768211455";
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.register_global_module(Math128Package::new().as_shared_module());
+        engine
+    }
+
+    fn eval_err(script: &str) -> String {
+        return match engine().eval::<Uint128>(script) {
+            Ok(val) => panic!("expected an error, got {val}"),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    #[test]
+    fn uint128_arithmetic_works() {
+        let val = engine()
+            .eval::<Uint128>(&format!(r#"(uint128("{MAX}") - uint128(5)) / uint128(10) * uint128(2) % uint128(7)"#))
+            .unwrap();
+        assert_eq!(val, Uint128((u128::MAX - 5) / 10 * 2 % 7));
+
+        let val = engine()
+            .eval::<Uint128>(r#"uint128(1000) * decimal_percent(15) + uint128(100).multiply_ratio(uint128(2), uint128(3))"#)
+            .unwrap();
+        assert_eq!(val, Uint128(150 + 66));
+    }
+
+    #[test]
+    fn uint128_overflow_raises() {
+        let err = eval_err(&format!(r#"uint128("{MAX}") + uint128(1)"#));
+        assert!(err.contains(&format!("Cannot add with {MAX} and 1")), "{err}");
+
+        let err = eval_err(&format!(r#"uint128("{MAX}") * uint128(2)"#));
+        assert!(err.contains(&format!("Cannot multiply with {MAX} and 2")), "{err}");
+
+        let err = eval_err(r#"uint128(1) - uint128(2)"#);
+        assert!(err.contains("Cannot subtract 2 from 1"), "{err}");
+    }
+
+    #[test]
+    fn uint128_division_by_zero_raises() {
+        assert!(eval_err(r#"uint128(1) / uint128(0)"#).contains("Division by zero"));
+        assert!(eval_err(r#"uint128(1) % uint128(0)"#).contains("Division by zero"));
+        assert!(eval_err(r#"uint128(1).multiply_ratio(uint128(1), uint128(0))"#).contains("Denominator must not be zero"));
+    }
+
+    #[test]
+    fn decimal_errors_raise() {
+        let err = engine()
+            .eval::<Decimal>(r#"decimal_from_ratio(uint128(1), uint128(0))"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Denominator must not be zero"), "{err}");
+
+        let err = engine()
+            .eval::<Decimal>(r#"decimal("1") - decimal("2")"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot subtract"), "{err}");
+    }
+}
@@ -1,30 +1,28 @@
 use std::prelude::v1::*;
 
 use rhai::def_package;
-use rhai::packages::{BasicArrayPackage, BasicBlobPackage, BasicMapPackage, BasicMathPackage, BitFieldPackage, CorePackage, LogicPackage, MoreStringPackage};
+
+use crate::rhai::packages::selection::PackageKind;
 
 def_package! {
-    /// Standard package containing all built-in features.
+    /// Standard package containing all built-in features enabled via cargo features.
     ///
     /// # Contents
     ///
     /// * [`CorePackage`][super::CorePackage]
-    /// * [`BitFieldPackage`][super::BitFieldPackage]
-    /// * [`LogicPackage`][super::LogicPackage]
-    /// * [`BasicMathPackage`][super::BasicMathPackage]
-    /// * [`BasicArrayPackage`][super::BasicArrayPackage]
-    /// * [`BasicBlobPackage`][super::BasicBlobPackage]
-    /// * [`BasicMapPackage`][super::BasicMapPackage]
-    /// * [`BasicTimePackage`][super::BasicTimePackage]
-    /// * [`MoreStringPackage`][super::MoreStringPackage]
+    /// * [`BitFieldPackage`][super::BitFieldPackage] (`pkg-bit-field`)
+    /// * [`LogicPackage`][super::LogicPackage] (`pkg-logic`)
+    /// * [`BasicMathPackage`][super::BasicMathPackage] (`pkg-math`)
+    /// * [`BasicArrayPackage`][super::BasicArrayPackage] (`pkg-array`)
+    /// * [`BasicBlobPackage`][super::BasicBlobPackage] (`pkg-blob`)
+    /// * [`BasicMapPackage`][super::BasicMapPackage] (`pkg-map`)
+    /// * [`MoreStringPackage`][super::MoreStringPackage] (`pkg-more-string`)
+    /// * [`Math128Package`][super::pkg_math128::Math128Package] (`pkg-math128`)
+    /// * [`JsonPackage`][super::pkg_json::JsonPackage] (`pkg-json`)
+    /// * [`CryptoPackage`][super::pkg_crypto::CryptoPackage] (`pkg-crypto`)
     pub StandardPackage(lib) {
-        CorePackage::init(lib); // 18k extra in WASM
-        BitFieldPackage::init(lib); // 1k extra
-        LogicPackage::init(lib); // < 1k extra
-        BasicMathPackage::init(lib); // 1k extra
-        BasicArrayPackage::init(lib); // 15k extra
-        BasicBlobPackage::init(lib); // 7k extra
-        BasicMapPackage::init(lib); // 4k extra
-        MoreStringPackage::init(lib); // 34k extra
+        for kind in PackageKind::enabled() {
+            kind.init(lib).expect("enabled packages are compiled in");
+        }
     }
 }
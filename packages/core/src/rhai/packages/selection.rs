@@ -0,0 +1,184 @@
+use std::prelude::v1::*;
+
+use cosmwasm_std::StdError;
+use rhai::Module;
+use rhai::packages::{CorePackage, Package};
+#[cfg(feature = "pkg-array")]
+use rhai::packages::BasicArrayPackage;
+#[cfg(feature = "pkg-blob")]
+use rhai::packages::BasicBlobPackage;
+#[cfg(feature = "pkg-map")]
+use rhai::packages::BasicMapPackage;
+#[cfg(feature = "pkg-math")]
+use rhai::packages::BasicMathPackage;
+#[cfg(feature = "pkg-bit-field")]
+use rhai::packages::BitFieldPackage;
+#[cfg(feature = "pkg-logic")]
+use rhai::packages::LogicPackage;
+#[cfg(feature = "pkg-more-string")]
+use rhai::packages::MoreStringPackage;
+
+#[cfg(feature = "pkg-crypto")]
+use crate::rhai::packages::pkg_crypto::CryptoPackage;
+#[cfg(feature = "pkg-json")]
+use crate::rhai::packages::pkg_json::JsonPackage;
+#[cfg(feature = "pkg-math128")]
+use crate::rhai::packages::pkg_math128::Math128Package;
+
+/// A Rhai package which may be selected for inclusion in an `OmnibusEngine`.
+///
+/// Only packages enabled via their cargo feature are available, `Core` is always included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PackageKind {
+    Core,
+    BitField,
+    Logic,
+    Math,
+    Array,
+    Blob,
+    Map,
+    MoreString,
+    Math128,
+    Json,
+    Crypto,
+}
+
+pub const ALL_PACKAGES: &'static [PackageKind] = &[
+    PackageKind::Core, PackageKind::BitField, PackageKind::Logic, PackageKind::Math,
+    PackageKind::Array, PackageKind::Blob, PackageKind::Map, PackageKind::MoreString,
+    PackageKind::Math128, PackageKind::Json, PackageKind::Crypto,
+];
+
+impl PackageKind {
+    /// Name used to refer to the package (i.e. in `required_packages`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageKind::Core => "core",
+            PackageKind::BitField => "bit_field",
+            PackageKind::Logic => "logic",
+            PackageKind::Math => "math",
+            PackageKind::Array => "array",
+            PackageKind::Blob => "blob",
+            PackageKind::Map => "map",
+            PackageKind::MoreString => "more_string",
+            PackageKind::Math128 => "math128",
+            PackageKind::Json => "json",
+            PackageKind::Crypto => "crypto",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PackageKind> {
+        ALL_PACKAGES.iter().find(|p| p.name() == name).copied()
+    }
+
+    /// Was the package compiled in (via its cargo feature)?
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            PackageKind::Core => true,
+            PackageKind::BitField => cfg!(feature = "pkg-bit-field"),
+            PackageKind::Logic => cfg!(feature = "pkg-logic"),
+            PackageKind::Math => cfg!(feature = "pkg-math"),
+            PackageKind::Array => cfg!(feature = "pkg-array"),
+            PackageKind::Blob => cfg!(feature = "pkg-blob"),
+            PackageKind::Map => cfg!(feature = "pkg-map"),
+            PackageKind::MoreString => cfg!(feature = "pkg-more-string"),
+            PackageKind::Math128 => cfg!(feature = "pkg-math128"),
+            PackageKind::Json => cfg!(feature = "pkg-json"),
+            PackageKind::Crypto => cfg!(feature = "pkg-crypto"),
+        }
+    }
+
+    /// All packages compiled in.
+    pub fn enabled() -> Vec<PackageKind> {
+        ALL_PACKAGES.iter().filter(|p| p.is_enabled()).copied().collect()
+    }
+
+    pub(crate) fn init(&self, lib: &mut Module) -> Result<(), StdError> {
+        match self {
+            PackageKind::Core => CorePackage::init(lib),
+            #[cfg(feature = "pkg-bit-field")]
+            PackageKind::BitField => BitFieldPackage::init(lib),
+            #[cfg(feature = "pkg-logic")]
+            PackageKind::Logic => LogicPackage::init(lib),
+            #[cfg(feature = "pkg-math")]
+            PackageKind::Math => BasicMathPackage::init(lib),
+            #[cfg(feature = "pkg-array")]
+            PackageKind::Array => BasicArrayPackage::init(lib),
+            #[cfg(feature = "pkg-blob")]
+            PackageKind::Blob => BasicBlobPackage::init(lib),
+            #[cfg(feature = "pkg-map")]
+            PackageKind::Map => BasicMapPackage::init(lib),
+            #[cfg(feature = "pkg-more-string")]
+            PackageKind::MoreString => MoreStringPackage::init(lib),
+            #[cfg(feature = "pkg-math128")]
+            PackageKind::Math128 => Math128Package::init(lib),
+            #[cfg(feature = "pkg-json")]
+            PackageKind::Json => JsonPackage::init(lib),
+            #[cfg(feature = "pkg-crypto")]
+            PackageKind::Crypto => CryptoPackage::init(lib),
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(StdError::GenericErr {
+                    msg: format!("rhai package '{}' is not enabled in this build", self.name()),
+                    backtrace: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn enabled_matches_cargo_features() {
+        let features = [
+            (PackageKind::BitField, cfg!(feature = "pkg-bit-field")),
+            (PackageKind::Logic, cfg!(feature = "pkg-logic")),
+            (PackageKind::Math, cfg!(feature = "pkg-math")),
+            (PackageKind::Array, cfg!(feature = "pkg-array")),
+            (PackageKind::Blob, cfg!(feature = "pkg-blob")),
+            (PackageKind::Map, cfg!(feature = "pkg-map")),
+            (PackageKind::MoreString, cfg!(feature = "pkg-more-string")),
+            (PackageKind::Math128, cfg!(feature = "pkg-math128")),
+            (PackageKind::Json, cfg!(feature = "pkg-json")),
+            (PackageKind::Crypto, cfg!(feature = "pkg-crypto")),
+        ];
+        assert_eq!(features.len() + 1, ALL_PACKAGES.len());
+
+        let mut expected = vec![PackageKind::Core];
+        expected.extend(features.iter().filter(|(_, enabled)| *enabled).map(|(pkg, _)| *pkg));
+        assert_eq!(PackageKind::enabled(), expected);
+
+        for pkg in ALL_PACKAGES {
+            assert_eq!(pkg.init(&mut Module::new()).is_ok(), pkg.is_enabled(), "{}", pkg.name());
+        }
+    }
+
+    #[test]
+    fn from_name_works() {
+        for pkg in ALL_PACKAGES {
+            assert_eq!(PackageKind::from_name(pkg.name()), Some(*pkg));
+        }
+
+        // Disabled packages are still known by name.
+        assert_eq!(PackageKind::from_name("crypto"), Some(PackageKind::Crypto));
+        assert_eq!(PackageKind::from_name("Crypto"), None);
+        assert_eq!(PackageKind::from_name("unknown"), None);
+        assert_eq!(PackageKind::from_name(""), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "pkg-crypto"))]
+    fn disabled_packages_fail_to_init() {
+        match PackageKind::Crypto.init(&mut Module::new()).unwrap_err() {
+            StdError::GenericErr { msg, .. } => {
+                assert_eq!(msg, "rhai package 'crypto' is not enabled in this build");
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+}
@@ -72,7 +72,9 @@ pub enum StdError {
         #[serde(skip)]
         backtrace: Option<snafu::Backtrace>,
     },
-    #[snafu(display("Cannot subtract {} from {}", subtrahend, minuend))]
+    /// Also used for overflows (see `StdError::overflow`), which leave `subtrahend` empty and
+    /// describe the failed operation in `minuend`
+    #[snafu(display("{}", underflow_message(minuend, subtrahend)))]
     Underflow {
         minuend: String,
         subtrahend: String,
@@ -128,11 +130,34 @@ impl StdError {
         .build()
     }
 
+    /// An overflow of `operation` (e.g. "add"), reported as an `Underflow` so the serialized
+    /// error cases stay unchanged.
+    pub fn overflow<O: Into<String>, U: ToString>(operation: O, operand1: U, operand2: U) -> Self {
+        Underflow {
+            minuend: format!(
+                "{} with {} and {}",
+                operation.into(),
+                operand1.to_string(),
+                operand2.to_string()
+            ),
+            subtrahend: String::new(),
+        }
+        .build()
+    }
+
     pub fn unauthorized() -> Self {
         Unauthorized {}.build()
     }
 }
 
+fn underflow_message(minuend: &str, subtrahend: &str) -> String {
+    if subtrahend.is_empty() {
+        format!("Cannot {}", minuend)
+    } else {
+        format!("Cannot subtract {} from {}", subtrahend, minuend)
+    }
+}
+
 impl PartialEq for StdError {
     /// Two errors are considered equal if and only if their payloads (i.e. all fields other than backtrace) are equal.
     ///
@@ -364,6 +389,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn overflow_works() {
+        let error = StdError::overflow("add", 123u128, 456u128);
+        match error {
+            StdError::Underflow {
+                minuend,
+                subtrahend,
+                ..
+            } => {
+                assert_eq!(minuend, "add with 123 and 456");
+                assert_eq!(subtrahend, "");
+            }
+            _ => panic!("expect different error"),
+        }
+        assert_eq!(
+            StdError::overflow("multiply", 1, 2).to_string(),
+            "Cannot multiply with 1 and 2"
+        );
+    }
+
     #[test]
     fn unauthorized_works() {
         let error = StdError::unauthorized();
@@ -467,6 +512,11 @@ mod test {
         assert_conversion(Unauthorized {}.build());
     }
 
+    #[test]
+    fn overflow_conversion() {
+        assert_conversion(StdError::overflow("add", 1u8, 2u8));
+    }
+
     #[test]
     fn not_found_conversion() {
         assert_conversion(NotFound { kind: "State" }.build());
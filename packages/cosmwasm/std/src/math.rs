@@ -52,6 +52,47 @@ impl Decimal {
     pub fn is_zero(&self) -> bool {
         self.0 == 0
     }
+
+    /// Returns the ratio (nominator / denominator) as a Decimal, or an error
+    /// on a zero denominator or overflow
+    pub fn checked_from_ratio<A: Into<u128>, B: Into<u128>>(
+        nominator: A,
+        denominator: B,
+    ) -> StdResult<Decimal> {
+        let nominator: u128 = nominator.into();
+        let denominator: u128 = denominator.into();
+        if denominator == 0 {
+            return Err(StdError::generic_err("Denominator must not be zero"));
+        }
+        mul_div(nominator, DECIMAL_FRACTIONAL, denominator)
+            .map(Decimal)
+            .ok_or_else(|| StdError::overflow("divide", nominator, denominator))
+    }
+
+    pub fn checked_add(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_add(other.0)
+            .map(Decimal)
+            .ok_or_else(|| StdError::overflow("add", self, other))
+    }
+
+    pub fn checked_sub(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_sub(other.0)
+            .map(Decimal)
+            .ok_or_else(|| StdError::underflow(self, other))
+    }
+
+    pub fn checked_mul(self, other: Self) -> StdResult<Self> {
+        mul_div(self.0, other.0, DECIMAL_FRACTIONAL)
+            .map(Decimal)
+            .ok_or_else(|| StdError::overflow("multiply", self, other))
+    }
+
+    /// Same as `self * rhs`, but returns an error on overflow
+    pub fn checked_mul_uint128(self, rhs: Uint128) -> StdResult<Uint128> {
+        rhs.checked_multiply_ratio(self.0, DECIMAL_FRACTIONAL)
+    }
 }
 
 impl FromStr for Decimal {
@@ -292,6 +333,89 @@ impl Uint128 {
         let val = self.u128() * nominator / denominator;
         Uint128::from(val)
     }
+
+    /// returns self * nom / denom, or an error on a zero denominator or overflow
+    pub fn checked_multiply_ratio<A: Into<u128>, B: Into<u128>>(
+        &self,
+        nom: A,
+        denom: B,
+    ) -> StdResult<Uint128> {
+        let nominator: u128 = nom.into();
+        let denominator: u128 = denom.into();
+        if denominator == 0 {
+            return Err(StdError::generic_err("Denominator must not be zero"));
+        }
+        mul_div(self.u128(), nominator, denominator)
+            .map(Uint128)
+            .ok_or_else(|| StdError::overflow("multiply", self.u128(), nominator))
+    }
+
+    pub fn checked_add(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_add(other.0)
+            .map(Uint128)
+            .ok_or_else(|| StdError::overflow("add", self, other))
+    }
+
+    pub fn checked_sub(self, other: Self) -> StdResult<Self> {
+        self - other
+    }
+
+    pub fn checked_mul(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_mul(other.0)
+            .map(Uint128)
+            .ok_or_else(|| StdError::overflow("multiply", self, other))
+    }
+
+    pub fn checked_div(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_div(other.0)
+            .map(Uint128)
+            .ok_or_else(|| StdError::generic_err("Division by zero"))
+    }
+
+    pub fn checked_rem(self, other: Self) -> StdResult<Self> {
+        self.0
+            .checked_rem(other.0)
+            .map(Uint128)
+            .ok_or_else(|| StdError::generic_err("Division by zero"))
+    }
+}
+
+/// Returns a * b / denom computed with a 256 bit intermediate product, so it only
+/// fails (returns None) if the quotient itself doesn't fit in a u128.
+fn mul_div(a: u128, b: u128, denom: u128) -> Option<u128> {
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product / denom);
+    }
+
+    const MASK: u128 = u64::MAX as u128;
+    let (a_lo, a_hi) = (a & MASK, a >> 64);
+    let (b_lo, b_hi) = (b & MASK, b >> 64);
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let mid = (lo_lo >> 64) + (lo_hi & MASK) + (hi_lo & MASK);
+    let lo = (lo_lo & MASK) | (mid << 64);
+    let hi = a_hi * b_hi + (lo_hi >> 64) + (hi_lo >> 64) + (mid >> 64);
+    if hi >= denom {
+        return None;
+    }
+
+    // long division of (hi, lo) by denom, the remainder always stays below denom
+    let mut rem = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || rem >= denom {
+            rem = rem.wrapping_sub(denom);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
 }
 
 /// Serializes as a base64 string
@@ -757,4 +881,142 @@ mod test {
         let right = Uint128(0);
         assert_eq!(left * right, Uint128(0));
     }
+
+    #[test]
+    fn decimal_checked_math() {
+        let a = Decimal::percent(150);
+        let b = Decimal::percent(50);
+
+        assert_eq!(a.checked_add(b).unwrap(), Decimal::percent(200));
+        assert_eq!(a.checked_sub(b).unwrap(), Decimal::one());
+        assert_eq!(a.checked_mul(b).unwrap(), Decimal::percent(75));
+        assert_eq!(a.checked_mul_uint128(Uint128(300)).unwrap(), Uint128(450));
+
+        match b.checked_sub(a) {
+            Ok(_) => panic!("should error"),
+            Err(StdError::Underflow {
+                minuend,
+                subtrahend,
+                ..
+            }) => assert_eq!((minuend, subtrahend), (b.to_string(), a.to_string())),
+            _ => panic!("expected underflow error"),
+        }
+
+        assert_eq!(
+            Decimal::MAX.checked_add(Decimal::one()).unwrap_err(),
+            StdError::overflow("add", Decimal::MAX, Decimal::one())
+        );
+        assert_eq!(
+            Decimal::MAX.checked_mul(Decimal::percent(200)).unwrap_err(),
+            StdError::overflow("multiply", Decimal::MAX, Decimal::percent(200))
+        );
+    }
+
+    #[test]
+    fn decimal_checked_mul_large_products() {
+        // the raw product of 1e6 * 1e6 (1e24 * 1e24) doesn't fit in a u128
+        let million = Decimal::from_ratio(1_000_000u128, 1u128);
+        assert_eq!(
+            million.checked_mul(million).unwrap(),
+            Decimal::from_ratio(1_000_000_000_000u128, 1u128)
+        );
+
+        let big = Decimal::from_str("123456789012.345678901234567890").unwrap();
+        assert_eq!(
+            big.checked_mul(Decimal::percent(50)).unwrap(),
+            Decimal::from_str("61728394506.172839450617283945").unwrap()
+        );
+        assert_eq!(
+            Decimal::MAX.checked_mul(Decimal::one()).unwrap(),
+            Decimal::MAX
+        );
+        assert_eq!(
+            Decimal::one().checked_mul_uint128(Uint128(u128::MAX)).unwrap(),
+            Uint128(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn decimal_checked_from_ratio_works() {
+        assert_eq!(
+            Decimal::checked_from_ratio(3u128, 2u128).unwrap(),
+            Decimal::percent(150)
+        );
+
+        match Decimal::checked_from_ratio(1u128, 0u128).unwrap_err() {
+            StdError::GenericErr { msg, .. } => assert_eq!(msg, "Denominator must not be zero"),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // the intermediate product overflows but the ratio fits
+        assert_eq!(
+            Decimal::checked_from_ratio(u128::MAX / 2, u128::MAX).unwrap(),
+            Decimal::from_str("0.499999999999999999").unwrap()
+        );
+
+        assert_eq!(
+            Decimal::checked_from_ratio(u128::MAX, 1u128).unwrap_err(),
+            StdError::overflow("divide", u128::MAX, 1u128)
+        );
+    }
+
+    #[test]
+    fn uint128_checked_math() {
+        let a = Uint128(12345);
+        let b = Uint128(23456);
+
+        assert_eq!(a.checked_add(b).unwrap(), Uint128(35801));
+        assert_eq!(b.checked_sub(a).unwrap(), Uint128(11111));
+        assert_eq!(a.checked_mul(Uint128(2)).unwrap(), Uint128(24690));
+        assert_eq!(b.checked_div(a).unwrap(), Uint128(1));
+        assert_eq!(b.checked_rem(a).unwrap(), Uint128(11111));
+
+        match a.checked_sub(b) {
+            Ok(_) => panic!("should error"),
+            Err(StdError::Underflow {
+                minuend,
+                subtrahend,
+                ..
+            }) => assert_eq!((minuend, subtrahend), (a.to_string(), b.to_string())),
+            _ => panic!("expected underflow error"),
+        }
+
+        // almost_max is 2^128 - 10
+        let almost_max = Uint128(340282366920938463463374607431768211446);
+        assert!(almost_max.checked_add(Uint128(12)).is_err());
+        assert!(almost_max.checked_mul(Uint128(2)).is_err());
+
+        match a.checked_div(Uint128::zero()).unwrap_err() {
+            StdError::GenericErr { msg, .. } => assert_eq!(msg, "Division by zero"),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert!(a.checked_rem(Uint128::zero()).is_err());
+    }
+
+    #[test]
+    fn u128_checked_multiply_ratio_works() {
+        let base = Uint128(500);
+
+        assert_eq!(base.checked_multiply_ratio(3u128, 2u128).unwrap(), Uint128(750));
+        assert_eq!(base.checked_multiply_ratio(2u128, 3u128).unwrap(), Uint128(333));
+
+        match base.checked_multiply_ratio(1u128, 0u128).unwrap_err() {
+            StdError::GenericErr { msg, .. } => assert_eq!(msg, "Denominator must not be zero"),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert_eq!(
+            base.checked_multiply_ratio(u128::MAX, 1u128).unwrap_err(),
+            StdError::overflow("multiply", 500u128, u128::MAX)
+        );
+
+        // the intermediate product overflows but the result fits
+        assert_eq!(
+            Uint128(u128::MAX).checked_multiply_ratio(u128::MAX, u128::MAX).unwrap(),
+            Uint128(u128::MAX)
+        );
+        assert_eq!(
+            Uint128(u128::MAX).checked_multiply_ratio(2u128, 3u128).unwrap(),
+            Uint128(u128::MAX / 3 * 2)
+        );
+    }
 }
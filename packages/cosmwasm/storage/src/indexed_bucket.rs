@@ -0,0 +1,662 @@
+use serde::{de::DeserializeOwned, ser::Serialize};
+use std::marker::PhantomData;
+
+use cosmwasm_std::{to_vec, ReadonlyStorage, StdError, StdResult, Storage};
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, KV};
+
+use crate::length_prefixed::{to_length_prefixed, to_length_prefixed_nested};
+#[cfg(feature = "iterator")]
+use crate::namespace_helpers::range_with_prefix;
+use crate::namespace_helpers::{get_with_prefix, remove_with_prefix, set_with_prefix};
+#[cfg(feature = "iterator")]
+use crate::type_helpers::deserialize_kv;
+use crate::type_helpers::{may_deserialize, must_deserialize};
+
+/// Records are stored under (namespace, PK_NAMESPACE) and index entries under
+/// (namespace, INDEX_NAMESPACE, index name), so neither can collide with the other.
+const PK_NAMESPACE: &[u8] = b"pk";
+const INDEX_NAMESPACE: &[u8] = b"idx";
+
+pub fn indexed_bucket<'a, S: Storage, T>(
+    namespace: &[u8],
+    indexes: Vec<Index<T>>,
+    storage: &'a mut S,
+) -> IndexedBucket<'a, S, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    IndexedBucket::new(namespace, indexes, storage)
+}
+
+pub fn indexed_bucket_read<'a, S: ReadonlyStorage, T>(
+    namespace: &[u8],
+    indexes: Vec<Index<T>>,
+    storage: &'a S,
+) -> ReadonlyIndexedBucket<'a, S, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    ReadonlyIndexedBucket::new(namespace, indexes, storage)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IndexKind {
+    /// At most one record may have each index value
+    Unique,
+    /// Any number of records may share an index value
+    Multi,
+}
+
+/// A secondary index, `key_fn` derives the index value from a record.
+///
+/// Multi index entries are stored length prefixed, so they are ordered by the length of the
+/// index value first. Use fixed width values (e.g. big endian integers) to range over them.
+pub struct Index<T> {
+    name: String,
+    kind: IndexKind,
+    key_fn: Box<dyn Fn(&T) -> Vec<u8>>,
+}
+
+impl<T> Index<T> {
+    pub fn unique<F>(name: &str, key_fn: F) -> Self
+    where
+        F: Fn(&T) -> Vec<u8> + 'static,
+    {
+        Index {
+            name: name.to_string(),
+            kind: IndexKind::Unique,
+            key_fn: Box::new(key_fn),
+        }
+    }
+
+    pub fn multi<F>(name: &str, key_fn: F) -> Self
+    where
+        F: Fn(&T) -> Vec<u8> + 'static,
+    {
+        Index {
+            name: name.to_string(),
+            kind: IndexKind::Multi,
+            key_fn: Box::new(key_fn),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> IndexKind {
+        self.kind
+    }
+
+    /// The key of the entry for `value` (relative to the index prefix).
+    fn entry_key(&self, value: &[u8], pk: &[u8]) -> Vec<u8> {
+        match self.kind {
+            IndexKind::Unique => value.to_vec(),
+            IndexKind::Multi => {
+                let mut key = to_length_prefixed(value);
+                key.extend_from_slice(pk);
+                key
+            }
+        }
+    }
+}
+
+/// An index and the prefix its entries are stored under.
+struct IndexPrefix<T> {
+    index: Index<T>,
+    prefix: Vec<u8>,
+}
+
+fn index_prefixes<T>(namespace: &[u8], indexes: Vec<Index<T>>) -> Vec<IndexPrefix<T>> {
+    indexes
+        .into_iter()
+        .map(|index| IndexPrefix {
+            prefix: to_length_prefixed_nested(&[namespace, INDEX_NAMESPACE, index.name.as_bytes()]),
+            index,
+        })
+        .collect()
+}
+
+fn find_index<'i, T>(indexes: &'i [IndexPrefix<T>], name: &str) -> StdResult<&'i IndexPrefix<T>> {
+    indexes
+        .iter()
+        .find(|i| i.index.name == name)
+        .ok_or_else(|| StdError::generic_err(format!("unknown index '{}'", name)))
+}
+
+/// A bucket which maintains secondary indexes of its records.
+///
+/// The indexes are updated on `save`, `remove` and `update`, so records must only be
+/// written through an `IndexedBucket` declaring the same indexes.
+pub struct IndexedBucket<'a, S: Storage, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    storage: &'a mut S,
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    data: PhantomData<&'a T>,
+    pk_prefix: Vec<u8>,
+    indexes: Vec<IndexPrefix<T>>,
+}
+
+impl<'a, S: Storage, T> IndexedBucket<'a, S, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(namespace: &[u8], indexes: Vec<Index<T>>, storage: &'a mut S) -> Self {
+        IndexedBucket {
+            pk_prefix: to_length_prefixed_nested(&[namespace, PK_NAMESPACE]),
+            indexes: index_prefixes(namespace, indexes),
+            storage,
+            data: PhantomData,
+        }
+    }
+
+    /// save will serialize the model, update the indexes and store. Returns an error on
+    /// serialization issues or if a unique index already holds the value for another key,
+    /// in which case nothing is written.
+    pub fn save(&mut self, pk: &[u8], data: &T) -> StdResult<()> {
+        let bytes = to_vec(data)?;
+
+        for i in self.indexes.iter().filter(|i| i.index.kind == IndexKind::Unique) {
+            let value = (i.index.key_fn)(data);
+            if let Some(owner) = get_with_prefix(self.storage, &i.prefix, &value) {
+                if owner != pk {
+                    return Err(StdError::generic_err(format!(
+                        "unique index '{}' already holds this value",
+                        i.index.name
+                    )));
+                }
+            }
+        }
+
+        if let Some(old) = self.may_load(pk)? {
+            self.remove_entries(pk, &old);
+        }
+
+        set_with_prefix(self.storage, &self.pk_prefix, pk, &bytes);
+        for i in self.indexes.iter() {
+            let value = (i.index.key_fn)(data);
+            set_with_prefix(self.storage, &i.prefix, &i.index.entry_key(&value, pk), pk);
+        }
+
+        Ok(())
+    }
+
+    /// remove will delete the record and its index entries, returns an error if the
+    /// stored record can't be parsed (to find its index values)
+    pub fn remove(&mut self, pk: &[u8]) -> StdResult<()> {
+        if let Some(old) = self.may_load(pk)? {
+            self.remove_entries(pk, &old);
+            remove_with_prefix(self.storage, &self.pk_prefix, pk);
+        }
+
+        Ok(())
+    }
+
+    fn remove_entries(&mut self, pk: &[u8], data: &T) {
+        for i in self.indexes.iter() {
+            let value = (i.index.key_fn)(data);
+            remove_with_prefix(self.storage, &i.prefix, &i.index.entry_key(&value, pk));
+        }
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, pk: &[u8]) -> StdResult<T> {
+        load(self.storage, &self.pk_prefix, pk)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, pk: &[u8]) -> StdResult<Option<T>> {
+        may_load(self.storage, &self.pk_prefix, pk)
+    }
+
+    /// may_load_unique loads the record holding `value` in the unique index `index`,
+    /// returning its primary key and data
+    pub fn may_load_unique(&self, index: &str, value: &[u8]) -> StdResult<Option<(Vec<u8>, T)>> {
+        may_load_unique(self.storage, &self.pk_prefix, &self.indexes, index, value)
+    }
+
+    #[cfg(feature = "iterator")]
+    pub fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b> {
+        let mapped = range_with_prefix(self.storage, &self.pk_prefix, start, end, order)
+            .map(deserialize_kv::<T>);
+        Box::new(mapped)
+    }
+
+    /// range_by_index iterates over every record holding `value` in `index`
+    #[cfg(feature = "iterator")]
+    pub fn range_by_index<'b>(
+        &'b self,
+        index: &str,
+        value: &[u8],
+        order: Order,
+    ) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+        range_by_index(self.storage, &self.pk_prefix, &self.indexes, index, value, order)
+    }
+
+    /// range_index iterates over records in order of their value in `index`, between
+    /// the `start` (inclusive) and `end` (exclusive) values
+    #[cfg(feature = "iterator")]
+    pub fn range_index<'b>(
+        &'b self,
+        index: &str,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+        range_index(self.storage, &self.pk_prefix, &self.indexes, index, start, end, order)
+    }
+
+    /// update will load the data, perform the specified action, and store the result
+    /// (updating the indexes). Like `Bucket::update`, the action is also called if there
+    /// is no data stored at the key.
+    pub fn update<A>(&mut self, pk: &[u8], action: A) -> StdResult<T>
+    where
+        A: FnOnce(Option<T>) -> StdResult<T>,
+    {
+        let input = self.may_load(pk)?;
+        let output = action(input)?;
+        self.save(pk, &output)?;
+        Ok(output)
+    }
+}
+
+pub struct ReadonlyIndexedBucket<'a, S: ReadonlyStorage, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    storage: &'a S,
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    data: PhantomData<&'a T>,
+    pk_prefix: Vec<u8>,
+    indexes: Vec<IndexPrefix<T>>,
+}
+
+impl<'a, S: ReadonlyStorage, T> ReadonlyIndexedBucket<'a, S, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(namespace: &[u8], indexes: Vec<Index<T>>, storage: &'a S) -> Self {
+        ReadonlyIndexedBucket {
+            pk_prefix: to_length_prefixed_nested(&[namespace, PK_NAMESPACE]),
+            indexes: index_prefixes(namespace, indexes),
+            storage,
+            data: PhantomData,
+        }
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, pk: &[u8]) -> StdResult<T> {
+        load(self.storage, &self.pk_prefix, pk)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, pk: &[u8]) -> StdResult<Option<T>> {
+        may_load(self.storage, &self.pk_prefix, pk)
+    }
+
+    /// may_load_unique loads the record holding `value` in the unique index `index`,
+    /// returning its primary key and data
+    pub fn may_load_unique(&self, index: &str, value: &[u8]) -> StdResult<Option<(Vec<u8>, T)>> {
+        may_load_unique(self.storage, &self.pk_prefix, &self.indexes, index, value)
+    }
+
+    #[cfg(feature = "iterator")]
+    pub fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b> {
+        let mapped = range_with_prefix(self.storage, &self.pk_prefix, start, end, order)
+            .map(deserialize_kv::<T>);
+        Box::new(mapped)
+    }
+
+    /// range_by_index iterates over every record holding `value` in `index`
+    #[cfg(feature = "iterator")]
+    pub fn range_by_index<'b>(
+        &'b self,
+        index: &str,
+        value: &[u8],
+        order: Order,
+    ) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+        range_by_index(self.storage, &self.pk_prefix, &self.indexes, index, value, order)
+    }
+
+    /// range_index iterates over records in order of their value in `index`, between
+    /// the `start` (inclusive) and `end` (exclusive) values
+    #[cfg(feature = "iterator")]
+    pub fn range_index<'b>(
+        &'b self,
+        index: &str,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+        range_index(self.storage, &self.pk_prefix, &self.indexes, index, start, end, order)
+    }
+}
+
+fn load<S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &S,
+    pk_prefix: &[u8],
+    pk: &[u8],
+) -> StdResult<T> {
+    let value = get_with_prefix(storage, pk_prefix, pk);
+    must_deserialize(&value)
+}
+
+fn may_load<S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &S,
+    pk_prefix: &[u8],
+    pk: &[u8],
+) -> StdResult<Option<T>> {
+    let value = get_with_prefix(storage, pk_prefix, pk);
+    may_deserialize(&value)
+}
+
+fn may_load_unique<S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &S,
+    pk_prefix: &[u8],
+    indexes: &[IndexPrefix<T>],
+    index: &str,
+    value: &[u8],
+) -> StdResult<Option<(Vec<u8>, T)>> {
+    let i = find_index(indexes, index)?;
+    if i.index.kind != IndexKind::Unique {
+        return Err(StdError::generic_err(format!("index '{}' is not unique", index)));
+    }
+
+    match get_with_prefix(storage, &i.prefix, value) {
+        Some(pk) => {
+            let data = load(storage, pk_prefix, &pk)?;
+            Ok(Some((pk, data)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(feature = "iterator")]
+fn range_by_index<'b, S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &'b S,
+    pk_prefix: &[u8],
+    indexes: &[IndexPrefix<T>],
+    index: &str,
+    value: &[u8],
+    order: Order,
+) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+    let i = find_index(indexes, index)?;
+
+    let entries = match i.index.kind {
+        // Only the entry for `value` itself sorts before `value` followed by a zero byte.
+        IndexKind::Unique => {
+            let mut end = value.to_vec();
+            end.push(0);
+            range_with_prefix(storage, &i.prefix, Some(value), Some(&end), order)
+        }
+        IndexKind::Multi => {
+            let mut prefix = i.prefix.clone();
+            prefix.extend_from_slice(&to_length_prefixed(value));
+            range_with_prefix(storage, &prefix, None, None, order)
+        }
+    };
+
+    Ok(load_entries(storage, pk_prefix, entries))
+}
+
+#[cfg(feature = "iterator")]
+fn range_index<'b, S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &'b S,
+    pk_prefix: &[u8],
+    indexes: &[IndexPrefix<T>],
+    index: &str,
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    order: Order,
+) -> StdResult<Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b>> {
+    let i = find_index(indexes, index)?;
+
+    let (start, end) = match i.index.kind {
+        IndexKind::Unique => (start.map(|s| s.to_vec()), end.map(|e| e.to_vec())),
+        IndexKind::Multi => (start.map(to_length_prefixed), end.map(to_length_prefixed)),
+    };
+    let entries = range_with_prefix(storage, &i.prefix, start.as_deref(), end.as_deref(), order);
+
+    Ok(load_entries(storage, pk_prefix, entries))
+}
+
+/// Load the record each index entry (whose value is the primary key) points to.
+#[cfg(feature = "iterator")]
+fn load_entries<'b, S: ReadonlyStorage, T: DeserializeOwned>(
+    storage: &'b S,
+    pk_prefix: &[u8],
+    entries: Box<dyn Iterator<Item = KV> + 'b>,
+) -> Box<dyn Iterator<Item = StdResult<KV<T>>> + 'b> {
+    let pk_prefix = pk_prefix.to_vec();
+    let mapped = entries.map(move |(_, pk)| -> StdResult<KV<T>> {
+        let data = load(storage, &pk_prefix, &pk)?;
+        Ok((pk, data))
+    });
+    Box::new(mapped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Data {
+        pub name: String,
+        pub age: i32,
+    }
+
+    fn indexes() -> Vec<Index<Data>> {
+        vec![
+            Index::unique("name", |d: &Data| d.name.as_bytes().to_vec()),
+            Index::multi("age", |d: &Data| d.age.to_be_bytes().to_vec()),
+        ]
+    }
+
+    fn person(name: &str, age: i32) -> Data {
+        Data {
+            name: name.to_string(),
+            age,
+        }
+    }
+
+    #[test]
+    fn store_and_load_by_unique_index() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        let maria = person("Maria", 42);
+        bucket.save(b"1", &maria).unwrap();
+
+        assert_eq!(bucket.load(b"1").unwrap(), maria);
+        assert_eq!(
+            bucket.may_load_unique("name", b"Maria").unwrap(),
+            Some((b"1".to_vec(), maria))
+        );
+        assert_eq!(bucket.may_load_unique("name", b"Mari").unwrap(), None);
+    }
+
+    #[test]
+    fn unique_index_rejects_duplicates() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        // saving the same key again is fine
+        bucket.save(b"1", &person("Maria", 43)).unwrap();
+
+        let err = bucket.save(b"2", &person("Maria", 27)).unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => assert!(msg.contains("'name'")),
+            e => panic!("Unexpected error {}", e),
+        }
+        // nothing was written
+        assert_eq!(bucket.may_load(b"2").unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_and_non_unique_indexes_error() {
+        let mut store = MockStorage::new();
+        let bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        assert!(bucket.may_load_unique("email", b"x").is_err());
+        assert!(bucket.may_load_unique("age", &42i32.to_be_bytes()).is_err());
+    }
+
+    #[test]
+    fn save_replaces_index_entries() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        bucket.save(b"1", &person("Mary", 42)).unwrap();
+
+        assert_eq!(bucket.may_load_unique("name", b"Maria").unwrap(), None);
+        assert_eq!(
+            bucket.may_load_unique("name", b"Mary").unwrap(),
+            Some((b"1".to_vec(), person("Mary", 42)))
+        );
+
+        // the old value is free again
+        bucket.save(b"2", &person("Maria", 27)).unwrap();
+    }
+
+    #[test]
+    fn remove_clears_index_entries() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+
+        // deleting random key does nothing
+        bucket.remove(b"2").unwrap();
+        assert!(bucket.may_load(b"1").unwrap().is_some());
+
+        bucket.remove(b"1").unwrap();
+        assert_eq!(bucket.may_load(b"1").unwrap(), None);
+        assert_eq!(bucket.may_load_unique("name", b"Maria").unwrap(), None);
+    }
+
+    #[test]
+    fn update_reindexes() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        let output = bucket
+            .update(b"1", |d| {
+                let mut d = d.ok_or(StdError::not_found("Data"))?;
+                d.name = "Mary".to_string();
+                Ok(d)
+            })
+            .unwrap();
+        assert_eq!(output, person("Mary", 42));
+
+        assert_eq!(bucket.may_load_unique("name", b"Maria").unwrap(), None);
+        assert!(bucket.may_load_unique("name", b"Mary").unwrap().is_some());
+    }
+
+    #[test]
+    fn readonly_works() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+
+        let reader = indexed_bucket_read(b"people", indexes(), &store);
+        assert_eq!(reader.load(b"1").unwrap(), person("Maria", 42));
+        assert!(reader.load(b"2").is_err());
+        assert_eq!(
+            reader.may_load_unique("name", b"Maria").unwrap(),
+            Some((b"1".to_vec(), person("Maria", 42)))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_by_multi_index() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        bucket.save(b"2", &person("Jose", 42)).unwrap();
+        bucket.save(b"3", &person("Ana", 27)).unwrap();
+
+        let res: StdResult<Vec<KV<Data>>> = bucket
+            .range_by_index("age", &42i32.to_be_bytes(), Order::Ascending)
+            .unwrap()
+            .collect();
+        let data = res.unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0], (b"1".to_vec(), person("Maria", 42)));
+        assert_eq!(data[1], (b"2".to_vec(), person("Jose", 42)));
+
+        // moving a record moves its entry
+        bucket.save(b"1", &person("Maria", 27)).unwrap();
+        let res: StdResult<Vec<KV<Data>>> = bucket
+            .range_by_index("age", &27i32.to_be_bytes(), Order::Ascending)
+            .unwrap()
+            .collect();
+        assert_eq!(res.unwrap().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_by_unique_index_is_exact() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        bucket.save(b"2", &person("Mariana", 27)).unwrap();
+
+        let res: StdResult<Vec<KV<Data>>> = bucket
+            .range_by_index("name", b"Maria", Order::Ascending)
+            .unwrap()
+            .collect();
+        assert_eq!(res.unwrap(), vec![(b"1".to_vec(), person("Maria", 42))]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_index_orders_by_value() {
+        let mut store = MockStorage::new();
+        let mut bucket = indexed_bucket(b"people", indexes(), &mut store);
+
+        bucket.save(b"1", &person("Maria", 42)).unwrap();
+        bucket.save(b"2", &person("Jose", 19)).unwrap();
+        bucket.save(b"3", &person("Ana", 27)).unwrap();
+
+        let res: StdResult<Vec<KV<Data>>> = bucket
+            .range_index("age", Some(&20i32.to_be_bytes()), None, Order::Ascending)
+            .unwrap()
+            .collect();
+        let names: Vec<String> = res.unwrap().into_iter().map(|(_, d)| d.name).collect();
+        assert_eq!(names, vec!["Ana", "Maria"]);
+
+        let res: StdResult<Vec<KV<Data>>> = bucket
+            .range_index("name", None, None, Order::Descending)
+            .unwrap()
+            .collect();
+        let names: Vec<String> = res.unwrap().into_iter().map(|(_, d)| d.name).collect();
+        assert_eq!(names, vec!["Maria", "Jose", "Ana"]);
+
+        // and the records themselves by primary key
+        let res: StdResult<Vec<KV<Data>>> = bucket.range(None, None, Order::Ascending).collect();
+        assert_eq!(res.unwrap().len(), 3);
+    }
+}
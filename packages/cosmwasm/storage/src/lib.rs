@@ -1,17 +1,25 @@
 mod bucket;
+mod indexed_bucket;
 mod length_prefixed;
 mod namespace_helpers;
 mod prefixed_storage;
+mod primary_key;
 mod sequence;
 mod singleton;
 mod transactions;
 mod type_helpers;
 mod typed;
+mod typed_bucket;
 
 pub use bucket::{bucket, bucket_read, Bucket, ReadonlyBucket};
+pub use indexed_bucket::{
+    indexed_bucket, indexed_bucket_read, Index, IndexKind, IndexedBucket, ReadonlyIndexedBucket,
+};
 pub use length_prefixed::{to_length_prefixed, to_length_prefixed_nested};
 pub use prefixed_storage::{prefixed, prefixed_read, PrefixedStorage, ReadonlyPrefixedStorage};
+pub use primary_key::PrimaryKey;
 pub use sequence::{currval, nextval, sequence};
 pub use singleton::{singleton, singleton_read, ReadonlySingleton, Singleton};
 pub use transactions::{transactional, RepLog, StorageTransaction};
 pub use typed::{typed, typed_read, ReadonlyTypedStorage, TypedStorage};
+pub use typed_bucket::{typed_bucket, typed_bucket_read, ReadonlyTypedBucket, TypedBucket};
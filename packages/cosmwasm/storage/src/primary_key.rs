@@ -0,0 +1,224 @@
+use std::any::type_name;
+use std::convert::TryInto;
+
+use cosmwasm_std::{CanonicalAddr, HumanAddr, StdError, StdResult};
+
+use crate::length_prefixed::to_length_prefixed;
+
+/// A type which can be used as a storage key.
+///
+/// Keys are encoded so they sort in the same order as the values they encode, e.g.
+/// integers are big endian (with the sign bit flipped for signed integers) rather than
+/// the `to_string` encoding, which sorts 10 before 9.
+pub trait PrimaryKey: Sized {
+    /// The raw key for this value.
+    fn to_key(&self) -> Vec<u8>;
+
+    /// Decode a raw key produced by `to_key`.
+    fn from_key(key: &[u8]) -> StdResult<Self>;
+}
+
+fn key_err<T>(msg: &str) -> StdError {
+    StdError::parse_err(type_name::<T>(), msg)
+}
+
+macro_rules! unsigned_key {
+    ($($t:ty),*) => {
+        $(
+            impl PrimaryKey for $t {
+                fn to_key(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+
+                fn from_key(key: &[u8]) -> StdResult<Self> {
+                    let bytes = key
+                        .try_into()
+                        .map_err(|_| key_err::<Self>("key has the wrong length"))?;
+                    Ok(<$t>::from_be_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+// Flipping the sign bit sorts negative numbers before positive ones.
+macro_rules! signed_key {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl PrimaryKey for $t {
+                fn to_key(&self) -> Vec<u8> {
+                    ((*self as $u) ^ (1 << (<$u>::BITS - 1))).to_be_bytes().to_vec()
+                }
+
+                fn from_key(key: &[u8]) -> StdResult<Self> {
+                    let bytes = key
+                        .try_into()
+                        .map_err(|_| key_err::<Self>("key has the wrong length"))?;
+                    Ok((<$u>::from_be_bytes(bytes) ^ (1 << (<$u>::BITS - 1))) as $t)
+                }
+            }
+        )*
+    };
+}
+
+unsigned_key!(u8, u16, u32, u64, u128);
+signed_key!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+impl PrimaryKey for Vec<u8> {
+    fn to_key(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        Ok(key.to_vec())
+    }
+}
+
+impl PrimaryKey for String {
+    fn to_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        String::from_utf8(key.to_vec()).map_err(StdError::invalid_utf8)
+    }
+}
+
+impl PrimaryKey for HumanAddr {
+    fn to_key(&self) -> Vec<u8> {
+        self.as_str().as_bytes().to_vec()
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        Ok(HumanAddr(String::from_key(key)?))
+    }
+}
+
+impl PrimaryKey for CanonicalAddr {
+    fn to_key(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        Ok(CanonicalAddr::from(key))
+    }
+}
+
+/// Split a length prefixed element from the front of a tuple key.
+fn split_element<T>(key: &[u8]) -> StdResult<(&[u8], &[u8])> {
+    if key.len() < 2 {
+        return Err(key_err::<T>("key is too short"));
+    }
+    let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if key.len() < 2 + len {
+        return Err(key_err::<T>("key is too short"));
+    }
+    Ok((&key[2..2 + len], &key[2 + len..]))
+}
+
+// Every element but the last is length prefixed, so ranging over keys with the same
+// leading elements is a prefix range. Tuple keys are ordered by the length of the first
+// element before its bytes (i.e. "b" sorts before "aa"), use fixed width leading
+// elements (e.g. integers) to range over them in order.
+impl<A: PrimaryKey, B: PrimaryKey> PrimaryKey for (A, B) {
+    fn to_key(&self) -> Vec<u8> {
+        let mut key = to_length_prefixed(&self.0.to_key());
+        key.extend_from_slice(&self.1.to_key());
+        key
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        let (a, rest) = split_element::<Self>(key)?;
+        Ok((A::from_key(a)?, B::from_key(rest)?))
+    }
+}
+
+impl<A: PrimaryKey, B: PrimaryKey, C: PrimaryKey> PrimaryKey for (A, B, C) {
+    fn to_key(&self) -> Vec<u8> {
+        let mut key = to_length_prefixed(&self.0.to_key());
+        key.extend_from_slice(&to_length_prefixed(&self.1.to_key()));
+        key.extend_from_slice(&self.2.to_key());
+        key
+    }
+
+    fn from_key(key: &[u8]) -> StdResult<Self> {
+        let (a, rest) = split_element::<Self>(key)?;
+        let (b, rest) = split_element::<Self>(rest)?;
+        Ok((A::from_key(a)?, B::from_key(b)?, C::from_key(rest)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn roundtrip<K: PrimaryKey + PartialEq + std::fmt::Debug>(value: K) {
+        assert_eq!(K::from_key(&value.to_key()).unwrap(), value);
+    }
+
+    #[test]
+    fn integers_roundtrip() {
+        roundtrip(0u8);
+        roundtrip(u16::MAX);
+        roundtrip(1234u32);
+        roundtrip(u64::MAX);
+        roundtrip(u128::MAX);
+        roundtrip(i8::MIN);
+        roundtrip(-1i16);
+        roundtrip(-1234i32);
+        roundtrip(i64::MAX);
+        roundtrip(i128::MIN);
+    }
+
+    #[test]
+    fn integers_are_big_endian() {
+        assert_eq!(1u32.to_key(), vec![0, 0, 0, 1]);
+        assert_eq!(258u16.to_key(), vec![1, 2]);
+        assert_eq!(0i16.to_key(), vec![0x80, 0]);
+        assert_eq!((-1i16).to_key(), vec![0x7f, 0xff]);
+    }
+
+    #[test]
+    fn integer_keys_sort_numerically() {
+        let values: Vec<i64> = vec![i64::MIN, -300, -1, 0, 1, 9, 10, 300, i64::MAX];
+        let keys: Vec<Vec<u8>> = values.iter().map(|v| v.to_key()).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+
+        assert!(9u64.to_key() < 10u64.to_key());
+    }
+
+    #[test]
+    fn integers_reject_wrong_length() {
+        assert!(u32::from_key(&[0, 1]).is_err());
+        assert!(i8::from_key(&[]).is_err());
+    }
+
+    #[test]
+    fn strings_and_addresses_roundtrip() {
+        roundtrip("maria".to_string());
+        roundtrip(HumanAddr::from("secret1abc"));
+        roundtrip(CanonicalAddr::from(vec![1u8, 2, 3]));
+        roundtrip(vec![0u8, 255]);
+
+        assert!(String::from_key(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn tuples_are_length_prefixed() {
+        let key = ("ab".to_string(), 1u16).to_key();
+        assert_eq!(key, b"\x00\x02ab\x00\x01".to_vec());
+
+        roundtrip(("ab".to_string(), 1u16));
+        roundtrip((HumanAddr::from("maria"), "jose".to_string(), -5i32));
+        roundtrip((String::new(), String::new()));
+    }
+
+    #[test]
+    fn tuples_reject_truncated_keys() {
+        assert!(<(String, u8)>::from_key(&[0]).is_err());
+        assert!(<(String, u8)>::from_key(b"\x00\x05ab").is_err());
+        assert!(<(String, String, u8)>::from_key(b"\x00\x01a").is_err());
+    }
+}
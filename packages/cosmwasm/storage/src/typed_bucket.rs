@@ -0,0 +1,307 @@
+use serde::{de::DeserializeOwned, ser::Serialize};
+use std::marker::PhantomData;
+
+use cosmwasm_std::{ReadonlyStorage, StdResult, Storage};
+#[cfg(feature = "iterator")]
+use cosmwasm_std::Order;
+
+use crate::bucket::{Bucket, ReadonlyBucket};
+use crate::primary_key::PrimaryKey;
+
+pub fn typed_bucket<'a, S: Storage, K, V>(
+    namespace: &[u8],
+    storage: &'a mut S,
+) -> TypedBucket<'a, S, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    TypedBucket::new(namespace, storage)
+}
+
+pub fn typed_bucket_read<'a, S: ReadonlyStorage, K, V>(
+    namespace: &[u8],
+    storage: &'a S,
+) -> ReadonlyTypedBucket<'a, S, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    ReadonlyTypedBucket::new(namespace, storage)
+}
+
+/// A `Bucket` keyed by a `PrimaryKey` rather than raw bytes.
+pub struct TypedBucket<'a, S: Storage, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    bucket: Bucket<'a, S, V>,
+    key: PhantomData<K>,
+}
+
+impl<'a, S: Storage, K, V> TypedBucket<'a, S, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    pub fn new(namespace: &[u8], storage: &'a mut S) -> Self {
+        TypedBucket {
+            bucket: Bucket::new(namespace, storage),
+            key: PhantomData,
+        }
+    }
+
+    pub fn multilevel(namespaces: &[&[u8]], storage: &'a mut S) -> Self {
+        TypedBucket {
+            bucket: Bucket::multilevel(namespaces, storage),
+            key: PhantomData,
+        }
+    }
+
+    /// save will serialize the model and store, returns an error on serialization issues
+    pub fn save(&mut self, key: &K, data: &V) -> StdResult<()> {
+        self.bucket.save(&key.to_key(), data)
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        self.bucket.remove(&key.to_key())
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, key: &K) -> StdResult<V> {
+        self.bucket.load(&key.to_key())
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, key: &K) -> StdResult<Option<V>> {
+        self.bucket.may_load(&key.to_key())
+    }
+
+    /// range iterates over the keys in their encoded order, decoding each key back into `K`
+    #[cfg(feature = "iterator")]
+    pub fn range<'b>(
+        &'b self,
+        start: Option<&K>,
+        end: Option<&K>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K, V)>> + 'b> {
+        let start = start.map(|k| k.to_key());
+        let end = end.map(|k| k.to_key());
+        let mapped = self
+            .bucket
+            .range(start.as_deref(), end.as_deref(), order)
+            .map(decode_kv::<K, V>);
+        Box::new(mapped)
+    }
+
+    /// update will load the data, perform the specified action, and store the result
+    /// in the database (see `Bucket::update`)
+    pub fn update<A>(&mut self, key: &K, action: A) -> StdResult<V>
+    where
+        A: FnOnce(Option<V>) -> StdResult<V>,
+    {
+        self.bucket.update(&key.to_key(), action)
+    }
+}
+
+pub struct ReadonlyTypedBucket<'a, S: ReadonlyStorage, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    bucket: ReadonlyBucket<'a, S, V>,
+    key: PhantomData<K>,
+}
+
+impl<'a, S: ReadonlyStorage, K, V> ReadonlyTypedBucket<'a, S, K, V>
+where
+    K: PrimaryKey,
+    V: Serialize + DeserializeOwned,
+{
+    pub fn new(namespace: &[u8], storage: &'a S) -> Self {
+        ReadonlyTypedBucket {
+            bucket: ReadonlyBucket::new(namespace, storage),
+            key: PhantomData,
+        }
+    }
+
+    pub fn multilevel(namespaces: &[&[u8]], storage: &'a S) -> Self {
+        ReadonlyTypedBucket {
+            bucket: ReadonlyBucket::multilevel(namespaces, storage),
+            key: PhantomData,
+        }
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, key: &K) -> StdResult<V> {
+        self.bucket.load(&key.to_key())
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, key: &K) -> StdResult<Option<V>> {
+        self.bucket.may_load(&key.to_key())
+    }
+
+    /// range iterates over the keys in their encoded order, decoding each key back into `K`
+    #[cfg(feature = "iterator")]
+    pub fn range<'b>(
+        &'b self,
+        start: Option<&K>,
+        end: Option<&K>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K, V)>> + 'b> {
+        let start = start.map(|k| k.to_key());
+        let end = end.map(|k| k.to_key());
+        let mapped = self
+            .bucket
+            .range(start.as_deref(), end.as_deref(), order)
+            .map(decode_kv::<K, V>);
+        Box::new(mapped)
+    }
+}
+
+#[cfg(feature = "iterator")]
+fn decode_kv<K: PrimaryKey, V>(kv: StdResult<(Vec<u8>, V)>) -> StdResult<(K, V)> {
+    let (k, v) = kv?;
+    Ok((K::from_key(&k)?, v))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::HumanAddr;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Data {
+        pub name: String,
+        pub age: i32,
+    }
+
+    #[test]
+    fn store_and_load() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, u64, Data>(b"data", &mut store);
+
+        let data = Data {
+            name: "Maria".to_string(),
+            age: 42,
+        };
+        bucket.save(&7, &data).unwrap();
+
+        assert_eq!(bucket.load(&7).unwrap(), data);
+        assert_eq!(bucket.may_load(&8).unwrap(), None);
+
+        bucket.remove(&7);
+        assert_eq!(bucket.may_load(&7).unwrap(), None);
+    }
+
+    #[test]
+    fn shares_keys_with_bucket() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, String, Data>(b"data", &mut store);
+
+        let data = Data {
+            name: "Maria".to_string(),
+            age: 42,
+        };
+        bucket.save(&"maria".to_string(), &data).unwrap();
+
+        let reader = crate::bucket::bucket_read::<_, Data>(b"data", &store);
+        assert_eq!(reader.load(b"maria").unwrap(), data);
+    }
+
+    #[test]
+    fn readonly_works() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, HumanAddr, Data>(b"data", &mut store);
+
+        let data = Data {
+            name: "Maria".to_string(),
+            age: 42,
+        };
+        bucket.save(&HumanAddr::from("maria"), &data).unwrap();
+
+        let reader = typed_bucket_read::<_, HumanAddr, Data>(b"data", &store);
+        assert_eq!(reader.load(&HumanAddr::from("maria")).unwrap(), data);
+        assert!(reader.load(&HumanAddr::from("jose")).is_err());
+    }
+
+    #[test]
+    fn update_success() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, (String, u32), Data>(b"data", &mut store);
+
+        let key = ("maria".to_string(), 1u32);
+        let output = bucket
+            .update(&key, |d| match d {
+                Some(_) => Ok(Data {
+                    name: "Wrong".to_string(),
+                    age: 0,
+                }),
+                None => Ok(Data {
+                    name: "Maria".to_string(),
+                    age: 42,
+                }),
+            })
+            .unwrap();
+        assert_eq!(output.age, 42);
+        assert_eq!(bucket.load(&key).unwrap(), output);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_sorts_numerically_and_decodes_keys() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, u32, Data>(b"data", &mut store);
+
+        for age in &[10, 9, 100] {
+            let data = Data {
+                name: format!("age {}", age),
+                age: *age as i32,
+            };
+            bucket.save(age, &data).unwrap();
+        }
+
+        let res: StdResult<Vec<(u32, Data)>> = bucket.range(None, None, Order::Ascending).collect();
+        let keys: Vec<u32> = res.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![9, 10, 100]);
+
+        let res: StdResult<Vec<(u32, Data)>> =
+            bucket.range(Some(&10), None, Order::Descending).collect();
+        let keys: Vec<u32> = res.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![100, 10]);
+
+        // also works for readonly
+        let reader = typed_bucket_read::<_, u32, Data>(b"data", &store);
+        let res: StdResult<Vec<(u32, Data)>> =
+            reader.range(None, Some(&100), Order::Ascending).collect();
+        assert_eq!(res.unwrap().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_decodes_tuple_keys() {
+        let mut store = MockStorage::new();
+        let mut bucket = typed_bucket::<_, (HumanAddr, u64), Data>(b"data", &mut store);
+
+        let data = Data {
+            name: "Maria".to_string(),
+            age: 42,
+        };
+        bucket.save(&(HumanAddr::from("maria"), 2), &data).unwrap();
+        bucket.save(&(HumanAddr::from("jose"), 1), &data).unwrap();
+
+        let res: StdResult<Vec<((HumanAddr, u64), Data)>> =
+            bucket.range(None, None, Order::Ascending).collect();
+        let keys: Vec<(HumanAddr, u64)> = res.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            keys,
+            vec![(HumanAddr::from("jose"), 1), (HumanAddr::from("maria"), 2)]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::rc::Rc;
+use std::str;
+
+use crate::archive::{Archive, check_size};
+use crate::result::{ResolverError, ResolverResult};
+
+pub const CONTAINER_MAGIC: &'static [u8] = b"RHBC";
+pub const CONTAINER_VERSION: u8 = 1;
+
+/// A minimal bundle container, all integers are big-endian:
+///
+/// ```text
+/// magic "RHBC" | version u8 | count u32 | manifest | data
+/// manifest entry: path_len u16 | path (UTF-8) | offset u32 | len u32
+/// ```
+///
+/// Offsets are relative to the start of the data section.
+#[derive(Debug, Clone)]
+pub struct ContainerArchive {
+    bytes: Rc<Vec<u8>>,
+    // name -> (offset, size), absolute.
+    entries: BTreeMap<String, (usize, usize)>,
+}
+
+impl ContainerArchive {
+    pub fn new(bytes: Vec<u8>) -> ResolverResult<Self> {
+        let mut reader = Reader { bytes: &bytes, pos: 0 };
+
+        if reader.take(CONTAINER_MAGIC.len())? != CONTAINER_MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let version = reader.take(1)?[0];
+        if version != CONTAINER_VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+
+        let count = reader.u32()?;
+        let mut manifest = Vec::new();
+        for _ in 0..count {
+            let path_len = reader.u16()? as usize;
+            let path = str::from_utf8(reader.take(path_len)?)
+                .map_err(|_| invalid("path is not UTF-8"))?
+                .to_string();
+            let offset = reader.u32()? as usize;
+            let len = reader.u32()? as usize;
+            manifest.push((path, offset, len));
+        }
+
+        let data_start = reader.pos;
+        let mut entries = BTreeMap::new();
+        for (path, offset, len) in manifest {
+            let start = data_start + offset;
+            if start.checked_add(len).map_or(true, |end| end > bytes.len()) {
+                return Err(invalid(&format!("entry '{path}' exceeds container")));
+            }
+            if entries.insert(path.clone(), (start, len)).is_some() {
+                return Err(invalid(&format!("duplicate entry '{path}'")));
+            }
+        }
+
+        Ok(Self {
+            bytes: Rc::new(bytes),
+            entries,
+        })
+    }
+}
+
+impl Archive for ContainerArchive {
+    fn format(&self) -> &'static str {
+        "container"
+    }
+
+    fn file_names(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    fn has_file(&mut self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    fn file_size(&mut self, name: &str) -> ResolverResult<u64> {
+        return match self.entries.get(name) {
+            Some((_, size)) => Ok(*size as u64),
+            None => Err(ResolverError::FileNotFound),
+        };
+    }
+
+    fn read_file(&mut self, name: &str, limit: u64) -> ResolverResult<Vec<u8>> {
+        let (offset, size) = *self.entries.get(name)
+            .ok_or(ResolverError::FileNotFound)?;
+        check_size(name, size as u64, limit)?;
+
+        Ok(self.bytes[offset..offset + size].to_vec())
+    }
+
+    fn clone_box(&self) -> Box<dyn Archive> {
+        Box::new(self.clone())
+    }
+}
+
+/// Build a container from (path, contents) pairs.
+pub fn write_container(files: &[(String, Vec<u8>)]) -> ResolverResult<Vec<u8>> {
+    let mut out = Vec::new();
+    out.extend_from_slice(CONTAINER_MAGIC);
+    out.push(CONTAINER_VERSION);
+    out.extend_from_slice(&to_u32(files.len())?.to_be_bytes());
+
+    let mut offset = 0usize;
+    for (path, data) in files {
+        let path_len: u16 = path.len().try_into()
+            .map_err(|_| invalid(&format!("path '{path}' is too long")))?;
+        out.extend_from_slice(&path_len.to_be_bytes());
+        out.extend_from_slice(path.as_bytes());
+        out.extend_from_slice(&to_u32(offset)?.to_be_bytes());
+        out.extend_from_slice(&to_u32(data.len())?.to_be_bytes());
+        offset += data.len();
+    }
+
+    for (_, data) in files {
+        out.extend_from_slice(data);
+    }
+
+    Ok(out)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> ResolverResult<&'a [u8]> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated manifest"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> ResolverResult<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> ResolverResult<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[inline(always)]
+fn to_u32(n: usize) -> ResolverResult<u32> {
+    n.try_into().map_err(|_| invalid("container too large"))
+}
+
+#[inline(always)]
+fn invalid(reason: &str) -> ResolverError {
+    ResolverError::InvalidArchive(format!("container: {reason}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn files() -> Vec<(String, Vec<u8>)> {
+        vec![
+            ("main.rhai".to_string(), b"fn main() {}".to_vec()),
+            ("lib/util.rhai".to_string(), b"fn util() {}".to_vec()),
+            ("empty.json".to_string(), Vec::new()),
+        ]
+    }
+
+    // A container with a single entry, as written by hand.
+    fn container(path: &str, offset: u32, len: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = CONTAINER_MAGIC.to_vec();
+        bytes.push(CONTAINER_VERSION);
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&(path.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.extend_from_slice(&offset.to_be_bytes());
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn assert_invalid(res: ResolverResult<ContainerArchive>, expected: &str) {
+        match res {
+            Err(ResolverError::InvalidArchive(msg)) => assert!(msg.contains(expected), "unexpected error: {msg}"),
+            res => panic!("unexpected result: {:?}", res.map(|a| a.file_names())),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut archive = ContainerArchive::new(write_container(&files()).unwrap()).unwrap();
+
+        assert_eq!(archive.format(), "container");
+        assert_eq!(archive.file_names(), vec!["empty.json".to_string(), "lib/util.rhai".to_string(),
+                                              "main.rhai".to_string()]);
+        for (path, data) in files() {
+            assert!(archive.has_file(&path));
+            assert_eq!(archive.file_size(&path).unwrap(), data.len() as u64);
+            assert_eq!(archive.read_file(&path, 1024).unwrap(), data);
+        }
+
+        assert!(!archive.has_file("missing.rhai"));
+        assert!(matches!(archive.read_file("missing.rhai", 1024), Err(ResolverError::FileNotFound)));
+        assert!(matches!(archive.read_file("main.rhai", 4), Err(ResolverError::FileTooLarge(_, 4))));
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_version() {
+        let mut bytes = write_container(&files()).unwrap();
+        bytes[0] = b'X';
+        assert_invalid(ContainerArchive::new(bytes), "bad magic");
+
+        let mut bytes = write_container(&files()).unwrap();
+        bytes[CONTAINER_MAGIC.len()] = CONTAINER_VERSION + 1;
+        assert_invalid(ContainerArchive::new(bytes), "unsupported version 2");
+
+        assert_invalid(ContainerArchive::new(b"RH".to_vec()), "truncated manifest");
+    }
+
+    #[test]
+    fn rejects_truncated_manifest() {
+        let bytes = write_container(&files()).unwrap();
+        // magic | version | count | path_len | "main.rhai" | offset | (len cut short)
+        let cut = CONTAINER_MAGIC.len() + 1 + 4 + 2 + "main.rhai".len() + 4 + 2;
+        assert_invalid(ContainerArchive::new(bytes[..cut].to_vec()), "truncated manifest");
+
+        // The count claims more entries than the manifest has.
+        let mut bytes = container("main.rhai", 0, 0, b"");
+        bytes[CONTAINER_MAGIC.len() + 1..CONTAINER_MAGIC.len() + 5].copy_from_slice(&2u32.to_be_bytes());
+        assert_invalid(ContainerArchive::new(bytes), "truncated manifest");
+    }
+
+    #[test]
+    fn rejects_entries_past_the_end() {
+        ContainerArchive::new(container("main.rhai", 0, 4, b"main")).unwrap();
+
+        assert_invalid(ContainerArchive::new(container("main.rhai", 0, 5, b"main")),
+                       "entry 'main.rhai' exceeds container");
+        assert_invalid(ContainerArchive::new(container("main.rhai", 1, 4, b"main")),
+                       "entry 'main.rhai' exceeds container");
+        assert_invalid(ContainerArchive::new(container("main.rhai", u32::MAX, u32::MAX, b"main")),
+                       "entry 'main.rhai' exceeds container");
+    }
+
+    #[test]
+    fn rejects_duplicate_paths() {
+        let files = vec![
+            ("main.rhai".to_string(), b"fn main() {}".to_vec()),
+            ("main.rhai".to_string(), b"fn other() {}".to_vec()),
+        ];
+
+        assert_invalid(ContainerArchive::new(write_container(&files).unwrap()),
+                       "duplicate entry 'main.rhai'");
+    }
+
+    #[test]
+    fn rejects_non_utf8_paths() {
+        let mut bytes = container("main.rhai", 0, 0, b"");
+        bytes[CONTAINER_MAGIC.len() + 1 + 4 + 2] = 0xff;
+
+        assert_invalid(ContainerArchive::new(bytes), "path is not UTF-8");
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+
+use crate::result::{ResolverError, ResolverResult};
+
+#[cfg(feature = "container_archive")]
+mod container;
+#[cfg(feature = "tar_archive")]
+mod tar_archive;
+#[cfg(feature = "zip_archive")]
+mod zip_archive;
+
+#[cfg(feature = "container_archive")]
+pub use container::{ContainerArchive, write_container, CONTAINER_MAGIC, CONTAINER_VERSION};
+#[cfg(feature = "tar_archive")]
+pub use tar_archive::{TarArchive, TAR_MAGIC, TAR_MAGIC_OFFSET};
+#[cfg(feature = "zip_archive")]
+pub use zip_archive::{ZipFileArchive, ZIP_MAGIC};
+
+/// A read-only archive of files (the bundle) addressed by normalized path.
+pub trait Archive: fmt::Debug {
+    /// The archive format name (i.e. for benchmarks and errors).
+    fn format(&self) -> &'static str;
+
+    /// The names of all files in the archive.
+    fn file_names(&self) -> Vec<String>;
+
+    fn has_file(&mut self, name: &str) -> bool;
+
+    /// The (uncompressed) size of a file.
+    fn file_size(&mut self, name: &str) -> ResolverResult<u64>;
+
+    /// Read a file, failing if it is larger than `limit` bytes.
+    fn read_file(&mut self, name: &str, limit: u64) -> ResolverResult<Vec<u8>>;
+
+    fn clone_box(&self) -> Box<dyn Archive>;
+}
+
+impl Clone for Box<dyn Archive> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Open an archive, detecting the format from its magic bytes (only formats enabled by
+/// cargo features are recognised).
+pub fn open_archive(bytes: Vec<u8>) -> ResolverResult<Box<dyn Archive>> {
+    #[cfg(feature = "container_archive")]
+    if bytes.starts_with(CONTAINER_MAGIC) {
+        return Ok(Box::new(ContainerArchive::new(bytes)?));
+    }
+
+    #[cfg(feature = "zip_archive")]
+    if bytes.starts_with(ZIP_MAGIC) {
+        return Ok(Box::new(ZipFileArchive::new(bytes)?));
+    }
+
+    #[cfg(feature = "tar_archive")]
+    if bytes.len() >= TAR_MAGIC_OFFSET + TAR_MAGIC.len()
+        && &bytes[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()] == TAR_MAGIC {
+        return Ok(Box::new(TarArchive::new(bytes)?));
+    }
+
+    #[allow(unreachable_code)]
+    Err(ResolverError::UnknownArchiveFormat)
+}
+
+/// Ensure a file of `size` bytes is within `limit`.
+#[inline(always)]
+pub(crate) fn check_size(name: &str, size: u64, limit: u64) -> ResolverResult<()> {
+    if size > limit {
+        return Err(ResolverError::FileTooLarge(name.to_string(), limit));
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "zip_archive", feature = "tar_archive", feature = "container_archive"))]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    const MAIN: &[u8] = b"fn main() {}";
+
+    fn zip_bytes() -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buf);
+            zip.start_file("main.rhai", FileOptions::default()).unwrap();
+            zip.write_all(MAIN).unwrap();
+            zip.finish().unwrap();
+        }
+
+        buf.into_inner()
+    }
+
+    fn tar_bytes() -> Vec<u8> {
+        let mut bytes = vec![0u8; 512];
+        bytes[.."main.rhai".len()].copy_from_slice(b"main.rhai");
+        bytes[124..135].copy_from_slice(format!("{:011o}", MAIN.len()).as_bytes());
+        bytes[156] = b'0';
+        bytes[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 8].copy_from_slice(b"ustar\x0000");
+        bytes.extend_from_slice(MAIN);
+        bytes.resize(512 * 4, 0);
+        bytes
+    }
+
+    #[test]
+    fn open_archive_detects_each_format() {
+        let container = write_container(&[("main.rhai".to_string(), MAIN.to_vec())]).unwrap();
+
+        for (bytes, format) in [(zip_bytes(), "zip"), (tar_bytes(), "tar"), (container, "container")] {
+            let mut archive = open_archive(bytes).unwrap();
+            assert_eq!(archive.format(), format);
+            assert_eq!(archive.file_names(), vec!["main.rhai".to_string()]);
+            assert_eq!(archive.read_file("main.rhai", 1024).unwrap(), MAIN.to_vec());
+        }
+    }
+
+    #[test]
+    fn open_archive_rejects_unknown_formats() {
+        assert!(matches!(open_archive(Vec::new()), Err(ResolverError::UnknownArchiveFormat)));
+        assert!(matches!(open_archive(b"not an archive".to_vec()), Err(ResolverError::UnknownArchiveFormat)));
+        // A recognised magic with a broken body is an invalid archive, not an unknown one.
+        assert!(matches!(open_archive(CONTAINER_MAGIC.to_vec()), Err(ResolverError::InvalidArchive(_))));
+    }
+}
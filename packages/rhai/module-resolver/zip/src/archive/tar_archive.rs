@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str;
+
+use crate::archive::{Archive, check_size};
+use crate::result::{ResolverError, ResolverResult};
+
+/// The ustar magic (followed by the version).
+pub const TAR_MAGIC: &'static [u8] = b"ustar";
+pub const TAR_MAGIC_OFFSET: usize = 257;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Uncompressed (ustar) tar archive, files are read in place without copying.
+#[derive(Debug, Clone)]
+pub struct TarArchive {
+    bytes: Rc<Vec<u8>>,
+    // name -> (offset, size)
+    entries: BTreeMap<String, (usize, usize)>,
+}
+
+impl TarArchive {
+    pub fn new(bytes: Vec<u8>) -> ResolverResult<Self> {
+        let mut entries = BTreeMap::new();
+        let mut offset = 0;
+
+        while offset + BLOCK_SIZE <= bytes.len() {
+            let header = &bytes[offset..offset + BLOCK_SIZE];
+            // Two zero blocks end the archive, one is enough to stop.
+            if header.iter().all(|b| *b == 0) {
+                break;
+            }
+
+            let name = header_str(&header[0..100])?;
+            let prefix = header_str(&header[345..500])?;
+            let size = parse_octal(&header[124..136])?;
+            let type_flag = header[156];
+
+            let data_start = offset + BLOCK_SIZE;
+            let data_end = data_start.checked_add(size)
+                .filter(|end| *end <= bytes.len())
+                .ok_or_else(|| invalid("entry exceeds archive"))?;
+
+            match type_flag {
+                // Regular files.
+                b'0' | 0 => {
+                    let name = if prefix.is_empty() { name } else { format!("{prefix}/{name}") };
+                    let name = name.trim_start_matches("./").to_string();
+                    if entries.insert(name.clone(), (data_start, size)).is_some() {
+                        return Err(invalid(&format!("duplicate entry '{name}'")));
+                    }
+                }
+                // GNU long names and pax headers rename the next entry, which would
+                // otherwise be read under its truncated ustar name.
+                b'L' | b'K' | b'x' | b'g' => {
+                    return Err(invalid(&format!("unsupported header type '{}'", type_flag as char)));
+                }
+                // Directories, links etc. are skipped.
+                _ => {}
+            }
+
+            offset = data_start + (size + BLOCK_SIZE - 1) / BLOCK_SIZE * BLOCK_SIZE;
+        }
+
+        Ok(Self {
+            bytes: Rc::new(bytes),
+            entries,
+        })
+    }
+}
+
+impl Archive for TarArchive {
+    fn format(&self) -> &'static str {
+        "tar"
+    }
+
+    fn file_names(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    fn has_file(&mut self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    fn file_size(&mut self, name: &str) -> ResolverResult<u64> {
+        return match self.entries.get(name) {
+            Some((_, size)) => Ok(*size as u64),
+            None => Err(ResolverError::FileNotFound),
+        };
+    }
+
+    fn read_file(&mut self, name: &str, limit: u64) -> ResolverResult<Vec<u8>> {
+        let (offset, size) = *self.entries.get(name)
+            .ok_or(ResolverError::FileNotFound)?;
+        check_size(name, size as u64, limit)?;
+
+        Ok(self.bytes[offset..offset + size].to_vec())
+    }
+
+    fn clone_box(&self) -> Box<dyn Archive> {
+        Box::new(self.clone())
+    }
+}
+
+fn header_str(field: &[u8]) -> ResolverResult<String> {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+
+    str::from_utf8(&field[..end])
+        .map(|s| s.to_string())
+        .map_err(|_| invalid("entry name is not UTF-8"))
+}
+
+fn parse_octal(field: &[u8]) -> ResolverResult<usize> {
+    let digits = header_str(field)?;
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    usize::from_str_radix(digits, 8)
+        .map_err(|_| invalid("invalid entry size"))
+}
+
+#[inline(always)]
+fn invalid(reason: &str) -> ResolverError {
+    ResolverError::InvalidArchive(format!("tar: {reason}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(name: &str, type_flag: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = type_flag;
+        header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 8].copy_from_slice(b"ustar\x0000");
+
+        let mut bytes = header;
+        bytes.extend_from_slice(data);
+        bytes.resize((bytes.len() + BLOCK_SIZE - 1) / BLOCK_SIZE * BLOCK_SIZE, 0);
+        bytes
+    }
+
+    fn archive(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = entries.concat();
+        bytes.extend_from_slice(&[0u8; BLOCK_SIZE * 2]);
+        bytes
+    }
+
+    fn assert_invalid(res: ResolverResult<TarArchive>, expected: &str) {
+        match res {
+            Err(ResolverError::InvalidArchive(msg)) => assert!(msg.contains(expected), "unexpected error: {msg}"),
+            res => panic!("unexpected result: {:?}", res.map(|a| a.file_names())),
+        }
+    }
+
+    #[test]
+    fn reads_regular_files() {
+        let mut tar = TarArchive::new(archive(&[
+            entry("./main.rhai", b'0', b"fn main() {}"),
+            entry("lib/", b'5', b""),
+            entry("lib/util.rhai", 0, b"fn util() {}"),
+        ])).unwrap();
+
+        assert_eq!(tar.file_names(), vec!["lib/util.rhai".to_string(), "main.rhai".to_string()]);
+        assert_eq!(tar.read_file("main.rhai", 1024).unwrap(), b"fn main() {}".to_vec());
+        assert_eq!(tar.file_size("lib/util.rhai").unwrap(), 12);
+    }
+
+    #[test]
+    fn rejects_duplicate_entries() {
+        assert_invalid(TarArchive::new(archive(&[
+            entry("main.rhai", b'0', b"fn main() {}"),
+            entry("./main.rhai", b'0', b"fn main() { 1 }"),
+        ])), "duplicate entry 'main.rhai'");
+    }
+
+    #[test]
+    fn rejects_long_name_and_pax_headers() {
+        for type_flag in &[b'L', b'K', b'x', b'g'] {
+            assert_invalid(TarArchive::new(archive(&[
+                entry("././@LongLink", *type_flag, b"a/very/long/name.rhai"),
+                entry("a/very/long/name.rh", b'0', b"fn main() {}"),
+            ])), "unsupported header type");
+        }
+    }
+}
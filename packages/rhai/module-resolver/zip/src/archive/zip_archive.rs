@@ -0,0 +1,67 @@
+use std::io::{Cursor, Read};
+
+use zip::ZipArchive;
+
+use crate::archive::{Archive, check_size};
+use crate::result::{ResolverError, ResolverResult};
+
+/// Local file header signature.
+pub const ZIP_MAGIC: &'static [u8] = b"PK\x03\x04";
+
+/// ZIP archive (deflate or stored).
+#[derive(Debug, Clone)]
+pub struct ZipFileArchive {
+    zip: ZipArchive<Cursor<Vec<u8>>>,
+}
+
+impl ZipFileArchive {
+    pub fn new(bytes: Vec<u8>) -> ResolverResult<Self> {
+        let zip = ZipArchive::new(Cursor::new(bytes))
+            .map_err(ResolverError::InvalidZip)?;
+
+        Ok(Self { zip })
+    }
+}
+
+impl Archive for ZipFileArchive {
+    fn format(&self) -> &'static str {
+        "zip"
+    }
+
+    fn file_names(&self) -> Vec<String> {
+        self.zip.file_names()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn has_file(&mut self, name: &str) -> bool {
+        self.zip.by_name(name).is_ok()
+    }
+
+    fn file_size(&mut self, name: &str) -> ResolverResult<u64> {
+        return match self.zip.by_name(name) {
+            Ok(file) => Ok(file.size()),
+            Err(_err) => Err(ResolverError::FileNotFound),
+        };
+    }
+
+    fn read_file(&mut self, name: &str, limit: u64) -> ResolverResult<Vec<u8>> {
+        let file = self.zip.by_name(name)
+            .map_err(|_err| {
+                ResolverError::FileNotFound
+            })?;
+        check_size(name, file.size(), limit)?;
+
+        // Don't trust the declared size.
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.take(limit + 1).read_to_end(&mut bytes)
+            .map_err(ResolverError::FileReadFailed)?;
+        check_size(name, bytes.len() as u64, limit)?;
+
+        Ok(bytes)
+    }
+
+    fn clone_box(&self) -> Box<dyn Archive> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use rhai::{Array, Dynamic, Engine, INT, Map};
+#[cfg(feature = "bake_consts")]
+use rhai::Scope;
+
+#[cfg(feature = "bake_consts")]
+use crate::archive::{open_archive, write_container};
+#[cfg(feature = "bake_consts")]
+use crate::resolver::{eval_consts, split_source_const, RHAI_EXTENSION};
+use crate::result::{ResolverError, ResolverResult};
+
+/// Present in (and only in) bundles whose consts were baked offline.
+pub const BAKED_CONSTS_MANIFEST: &'static str = "baked_consts.json";
+pub const BAKED_CONSTS_FORMAT: INT = 1;
+
+const KEY_FORMAT: &'static str = "format";
+const KEY_CONSTS: &'static str = "consts";
+
+/// The hoisted consts of each script, in declaration order: (name, value, exported).
+pub type BakedConsts = BTreeMap<PathBuf, Vec<(String, Dynamic, bool)>>;
+
+/// Parse the baked consts manifest of a bundle.
+pub fn load_manifest(json: &str) -> ResolverResult<BakedConsts> {
+    let manifest = Engine::new_raw().parse_json(json, true)
+        .map_err(|err| ResolverError::JsonParseFailed(err.to_string()))?;
+
+    let format = manifest.get(KEY_FORMAT).and_then(|f| f.as_int().ok());
+    if format != Some(BAKED_CONSTS_FORMAT) {
+        return Err(invalid(&format!("unsupported format {format:?}")));
+    }
+
+    let mut consts = BakedConsts::new();
+    if let Some(files) = manifest.get(KEY_CONSTS).and_then(|c| c.read_lock::<Map>()) {
+        for (path, entries) in files.iter() {
+            let entries = entries.read_lock::<Array>()
+                .ok_or_else(|| invalid(&format!("consts of '{path}' must be an array")))?;
+
+            let mut file_consts = Vec::new();
+            for entry in entries.iter() {
+                // The exported flag is optional (and defaults to private).
+                let entry = entry.read_lock::<Array>()
+                    .filter(|entry| (entry.len() == 2 || entry.len() == 3) && entry[0].is::<String>())
+                    .filter(|entry| entry.len() == 2 || entry[2].is::<bool>())
+                    .ok_or_else(|| invalid(&format!("consts of '{path}' must be [name, value, exported] entries")))?;
+
+                let exported = entry.get(2).map_or(false, |e| e.as_bool().unwrap());
+                file_consts.push((entry[0].clone().into_string().unwrap(), entry[1].clone(), exported));
+            }
+
+            consts.insert(PathBuf::from(path.as_str()), file_consts);
+        }
+    }
+
+    Ok(consts)
+}
+
+/// Bake the consts of a bundle offline: every script is checked to parse, its consts are
+/// evaluated and moved into the manifest (so they aren't split and evaluated on-chain)
+/// and the result is written as a container archive. Scripts remain source and are still
+/// parsed when loaded (rhai has no serializable AST). The consts are blanked out of each
+/// script rather than removed, so positions in errors still match the original file.
+///
+/// Consts must evaluate without the runtime scope (i.e. may not reference `ENV` or any
+/// globals), each script's exported consts are flagged in the manifest. The `engine` must
+/// be built as the contract's is (same rhai features and packages), otherwise values may
+/// differ from those evaluated on-chain.
+#[cfg(feature = "bake_consts")]
+pub fn bake_consts(bundle: Vec<u8>, engine: &Engine) -> ResolverResult<Vec<u8>> {
+    let mut archive = open_archive(bundle)?;
+    let script_ext = format!(".{RHAI_EXTENSION}");
+
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut manifest_consts: Vec<String> = Vec::new();
+
+    for name in archive.file_names() {
+        if name.ends_with('/') || name == BAKED_CONSTS_MANIFEST {
+            continue;
+        }
+
+        let bytes = archive.read_file(&name, u64::MAX)?;
+        if !name.ends_with(&script_ext) {
+            files.push((name, bytes));
+            continue;
+        }
+
+        let failed = |err: ResolverError| {
+            ResolverError::SourceCompileFailed(name.clone(), Box::new(err))
+        };
+
+        let source = String::from_utf8(bytes)
+            .map_err(|_| failed(invalid("source is not UTF-8")))?;
+
+        let mut scope = Scope::new();
+        let exported = eval_consts(&mut scope, engine, &source).map_err(failed)?;
+        let body = match split_source_const(&source) {
+            None => source.clone(),
+            Some((_, consts, _)) => blank_fragment(&source, consts),
+        };
+
+        engine.compile_with_scope(&scope, &body)
+            .map_err(|err| failed(ResolverError::ParseError(err)))?;
+
+        if !scope.is_empty() {
+            let mut entries = Vec::new();
+            for (const_name, _, value) in scope.iter() {
+                entries.push(format!("[{},{},{}]", json_string(const_name),
+                                     dynamic_to_json(&value).map_err(failed)?,
+                                     exported.iter().any(|e| e == const_name)));
+            }
+            manifest_consts.push(format!("{}:[{}]", json_string(&name), entries.join(",")));
+        }
+
+        files.push((name, body.into_bytes()));
+    }
+
+    let manifest = format!("{{\"{KEY_FORMAT}\":{BAKED_CONSTS_FORMAT},\"{KEY_CONSTS}\":{{{}}}}}",
+                           manifest_consts.join(","));
+    files.push((BAKED_CONSTS_MANIFEST.to_string(), manifest.into_bytes()));
+
+    write_container(&files)
+}
+
+/// Replace `fragment` (a slice of `source`) with whitespace, keeping its line breaks so
+/// the rest of the script keeps its original positions.
+#[cfg(feature = "bake_consts")]
+fn blank_fragment(source: &str, fragment: &str) -> String {
+    let start = fragment.as_ptr() as usize - source.as_ptr() as usize;
+    let blank: String = fragment.chars()
+        .map(|c| if c == '\n' { c } else { ' ' })
+        .collect();
+
+    format!("{}{}{}", &source[..start], blank, &source[start + fragment.len()..])
+}
+
+/// Serialize a const value, only JSON compatible values are supported.
+#[cfg(feature = "bake_consts")]
+fn dynamic_to_json(value: &Dynamic) -> ResolverResult<String> {
+    if value.is_unit() {
+        return Ok("null".to_string());
+    }
+    if let Ok(b) = value.as_bool() {
+        return Ok(b.to_string());
+    }
+    if let Ok(i) = value.as_int() {
+        return Ok(i.to_string());
+    }
+    if value.is::<String>() {
+        return Ok(json_string(&value.clone().into_string().unwrap()));
+    }
+    if let Some(arr) = value.read_lock::<Array>() {
+        let items = arr.iter()
+            .map(dynamic_to_json)
+            .collect::<ResolverResult<Vec<_>>>()?;
+        return Ok(format!("[{}]", items.join(",")));
+    }
+    if let Some(map) = value.read_lock::<Map>() {
+        let items = map.iter()
+            .map(|(k, v)| Ok(format!("{}:{}", json_string(k), dynamic_to_json(v)?)))
+            .collect::<ResolverResult<Vec<_>>>()?;
+        return Ok(format!("{{{}}}", items.join(",")));
+    }
+
+    Err(invalid(&format!("const of type '{}' can't be baked", value.type_name())))
+}
+
+#[cfg(feature = "bake_consts")]
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+#[inline(always)]
+fn invalid(reason: &str) -> ResolverError {
+    ResolverError::InvalidArchive(format!("baked consts: {reason}"))
+}
+
+#[cfg(all(test, feature = "bake_consts"))]
+mod test {
+    use rhai::{AST, Scope};
+
+    use crate::resolver::ZipModuleResolver;
+
+    use super::*;
+
+    const MAIN: &'static str = r#"export const LIMIT = 3 * 4;
+const LOCAL = 2;
+
+fn main() { LIMIT + LOCAL }
+
+fn fail() {
+    throw "boom";
+}"#;
+
+    fn bundle() -> Vec<u8> {
+        let files = [
+            ("config.json", r#"{"global":{"entrypoints":["main"]}}"#),
+            ("main.rhai", MAIN),
+            ("lib.rhai", "export const NAME = \"lib\";\nconst PRIVATE = 1;\nfn get() { PRIVATE }"),
+            ("data.txt", "not a script"),
+        ];
+
+        write_container(&files.iter()
+            .map(|(name, content)| (name.to_string(), content.as_bytes().to_vec()))
+            .collect::<Vec<_>>()).unwrap()
+    }
+
+    fn load(bytes: Vec<u8>, engine: &Engine) -> (ZipModuleResolver, AST) {
+        let mut resolver = ZipModuleResolver::new();
+        resolver.load_from_bytes(bytes).unwrap();
+        let ast = resolver.init_with_scope(engine, Scope::new()).unwrap().unwrap();
+
+        (resolver, ast)
+    }
+
+    /// The result of `main`, `lib`'s export and the (remapped) error thrown by `fail`.
+    fn run(bytes: Vec<u8>) -> (INT, String, String) {
+        let mut engine = Engine::new();
+
+        let (resolver, ast) = load(bytes.clone(), &engine);
+        let mut scope = resolver.scope().clone();
+        let err = engine.call_fn::<()>(&mut scope, &ast, "fail", ()).unwrap_err();
+        let err = resolver.remap_entrypoint_error(*err).to_string();
+
+        let (resolver, ast) = load(bytes, &engine);
+        let mut scope = resolver.scope().clone();
+        engine.set_module_resolver(resolver);
+
+        let result: INT = engine.call_fn(&mut scope, &ast, "main", ()).unwrap();
+        let name: String = engine.eval_with_scope(&mut scope, r#"import "lib" as m; m::NAME"#).unwrap();
+
+        (result, name, err)
+    }
+
+    #[test]
+    fn baked_bundles_behave_as_the_original() {
+        let baked = bake_consts(bundle(), &Engine::new()).unwrap();
+
+        let mut resolver = ZipModuleResolver::new();
+        resolver.load_from_bytes(baked.clone()).unwrap();
+        assert!(resolver.has_baked_consts());
+        assert_eq!(resolver.get_file(PathBuf::from("data.txt")).unwrap(), "not a script");
+
+        let manifest = load_manifest(&resolver.get_file(PathBuf::from(BAKED_CONSTS_MANIFEST)).unwrap())
+            .unwrap();
+        assert_eq!(manifest[&PathBuf::from("main.rhai")].iter()
+                       .map(|(name, value, exported)| (name.as_str(), value.as_int().unwrap(), *exported))
+                       .collect::<Vec<_>>(),
+                   vec![("LIMIT", 12, true), ("LOCAL", 2, false)]);
+
+        let original = run(bundle());
+        assert_eq!(original.0, 14);
+        assert_eq!(original.1, "lib");
+        assert!(original.2.contains("line 7"), "unexpected error: {}", original.2);
+
+        assert_eq!(run(baked), original);
+    }
+
+    #[test]
+    fn bake_reports_parse_errors_at_their_original_position() {
+        let files = vec![
+            ("main.rhai".to_string(), b"const A = 1;\n\nfn main() {\n    let = 1;\n}".to_vec()),
+        ];
+        let err = bake_consts(write_container(&files).unwrap(), &Engine::new()).unwrap_err();
+
+        assert_eq!(err.position().line(), Some(4), "unexpected error: {err}");
+        assert!(err.location().unwrap().starts_with("main.rhai:4:"));
+    }
+
+    fn assert_invalid(json: &str, expected: &str) {
+        match load_manifest(json) {
+            Err(ResolverError::InvalidArchive(msg)) => assert!(msg.contains(expected), "unexpected error: {msg}"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn load_manifest_works() {
+        let consts = load_manifest(r#"{"format":1,"consts":{"main.rhai":[["A",1],["B","b",true]]}}"#).unwrap();
+        let main = &consts[&PathBuf::from("main.rhai")];
+
+        assert_eq!(main.len(), 2);
+        assert_eq!((main[0].0.as_str(), main[0].1.as_int().unwrap(), main[0].2), ("A", 1, false));
+        assert_eq!((main[1].0.as_str(), main[1].1.clone().into_string().unwrap(), main[1].2),
+                   ("B", "b".to_string(), true));
+    }
+
+    #[test]
+    fn load_manifest_rejects_other_formats() {
+        assert_invalid(r#"{"format":2,"consts":{}}"#, "unsupported format Some(2)");
+        assert_invalid(r#"{"consts":{}}"#, "unsupported format None");
+        assert_invalid(r#"{"format":"1","consts":{}}"#, "unsupported format None");
+    }
+
+    #[test]
+    fn load_manifest_rejects_invalid_entries() {
+        assert_invalid(r#"{"format":1,"consts":{"main.rhai":5}}"#, "consts of 'main.rhai' must be an array");
+        for entry in &[r#"["A"]"#, r#"[1,2]"#, r#"["A",1,"yes"]"#, r#"["A",1,true,4]"#, r#""A""#] {
+            assert_invalid(&format!(r#"{{"format":1,"consts":{{"main.rhai":[{entry}]}}}}"#),
+                           "must be [name, value, exported] entries");
+        }
+    }
+}
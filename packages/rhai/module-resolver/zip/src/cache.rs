@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use rhai::{AST, Dynamic};
+use sha2::{Digest, Sha256};
+
+pub type BundleHash = [u8; 32];
+
+#[inline(always)]
+pub fn bundle_hash(bytes: &[u8]) -> BundleHash {
+    Sha256::digest(bytes).into()
+}
+
+/// Cache hit / miss counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A compiled module: its evaluated consts and the AST of its body. Modules are evaluated
+/// from it on every load, so top level reads (i.e. of storage) are never reused.
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledModule {
+    pub consts: Vec<(String, Dynamic)>,
+    pub exported: Vec<String>,
+    pub ast: AST,
+}
+
+/// Compiled modules are keyed by the scope they were compiled with too, consts (and the
+/// scope constants propagated into the body) may depend on it: the scope key (i.e. `ENV`)
+/// and the entrypoint group, whose entrypoints define the globals.
+type ModuleCacheKey = (BundleHash, Vec<u8>, Option<String>, PathBuf);
+
+#[derive(Debug, Default)]
+struct ModuleCacheInner {
+    modules: BTreeMap<ModuleCacheKey, Rc<CompiledModule>>,
+    stats: ModuleCacheStats,
+}
+
+/// A cache of compiled modules keyed by bundle hash, scope key (see
+/// `ZipModuleResolver::set_scope_key`), entrypoint group and module path, shared (cheaply cloned) between
+/// resolvers so engines in one execution don't recompile the same modules.
+#[derive(Debug, Clone, Default)]
+pub struct SharedModuleCache {
+    inner: Rc<RefCell<ModuleCacheInner>>,
+}
+
+impl SharedModuleCache {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a compiled module, counting the hit or miss.
+    pub(crate) fn get(&self, bundle: &BundleHash, scope_key: &[u8], group: Option<&str>,
+                      path: &Path) -> Option<Rc<CompiledModule>> {
+        let mut inner = self.inner.borrow_mut();
+
+        let key = (*bundle, scope_key.to_vec(), group.map(str::to_string), path.to_path_buf());
+        let module = inner.modules.get(&key).cloned();
+        if module.is_some() {
+            inner.stats.hits += 1;
+        } else {
+            inner.stats.misses += 1;
+        }
+
+        module
+    }
+
+    pub(crate) fn insert(&self, bundle: BundleHash, scope_key: Vec<u8>, group: Option<String>,
+                         path: PathBuf, module: Rc<CompiledModule>) {
+        self.inner.borrow_mut().modules.insert((bundle, scope_key, group, path), module);
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.inner.borrow().modules.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    pub fn stats(&self) -> ModuleCacheStats {
+        self.inner.borrow().stats
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.modules.clear();
+        inner.stats = ModuleCacheStats::default();
+    }
+}
@@ -1,7 +1,11 @@
+use std::any::TypeId;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 
-use rhai::{Array, Dynamic, INT, Map};
+use rhai::{Array, Dynamic, INT, Map, Variant};
+
+use crate::path::{format_path, parse_path, PathSegment};
+use crate::result::{ConfigError, ConfigResult};
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -17,123 +21,343 @@ impl Config {
         }
     }
 
+    /// Get the value at `key`, `Dynamic::UNIT` if missing.
     pub fn get(&self, key: &str) -> Dynamic {
-        let cache_key = key.to_string();
+        return self.try_get(key).unwrap_or(Dynamic::UNIT);
+    }
 
-        if let Some(value) = self.cache.borrow().get(&cache_key) {
-            return value.clone();
-        }
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        self.try_get_str(key).ok()
+    }
 
-        let val = self._get(key);
+    pub fn get_int(&self, key: &str) -> Option<INT> {
+        self.try_get_int(key).ok()
+    }
 
-        self.cache.borrow_mut().insert(cache_key, val.clone());
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.try_get_bool(key).ok()
+    }
 
-        val
+    pub fn get_array(&self, key: &str) -> Option<Array> {
+        self.try_get_array(key).ok()
     }
 
-    pub fn get_str(&self, key: &str) -> Option<String> {
-        let val = self.get(key);
-        if val.is::<String>() != true {
-            return None;
-        }
+    pub fn get_str_array(&self, key: &str) -> Option<Vec<String>> {
+        self.try_get_str_array(key).ok()
+    }
 
-        Some(val.into_string().unwrap())
+    /// Get the value at `key`, or `default` if missing (a value of the wrong type is an error).
+    pub fn get_or<T: Variant + Clone>(&self, key: &str, default: T) -> ConfigResult<T> {
+        return match self.try_get(key) {
+            Ok(val) => cast(key, val),
+            Err(ConfigError::Missing(_)) => Ok(default),
+            Err(err) => Err(err),
+        };
     }
 
-    pub fn get_int(&self, key: &str) -> Option<INT> {
-        let val = self.get(key);
-        if val.is::<INT>() != true {
-            return None;
+    /// Get the value at `key` (see `PathSegment` for the path syntax).
+    pub fn try_get(&self, key: &str) -> ConfigResult<Dynamic> {
+        let segments = parse_path(key)?;
+        let cache_key = format_path(&segments);
+
+        if let Some(value) = self.cache.borrow().get(&cache_key) {
+            return Ok(value.clone());
         }
 
-        Some(val.as_int().unwrap())
+        let val = self._get(&segments)?;
+
+        self.cache.borrow_mut().insert(cache_key, val.clone());
+
+        Ok(val)
     }
 
-    pub fn get_bool(&self, key: &str) -> Option<bool> {
-        let val = self.get(key);
-        if val.is::<bool>() != true {
-            return None;
-        }
+    pub fn try_get_str(&self, key: &str) -> ConfigResult<String> {
+        cast(key, self.try_get(key)?)
+    }
 
-        Some(val.as_bool().unwrap())
+    pub fn try_get_int(&self, key: &str) -> ConfigResult<INT> {
+        cast(key, self.try_get(key)?)
     }
 
-    pub fn get_array(&self, key: &str) -> Option<Array> {
-        let val = self.get(key);
-        if val.is::<Array>() != true {
-            return None;
-        }
+    pub fn try_get_bool(&self, key: &str) -> ConfigResult<bool> {
+        cast(key, self.try_get(key)?)
+    }
 
-        Some(val.read_lock::<Array>().unwrap().to_owned())
+    pub fn try_get_array(&self, key: &str) -> ConfigResult<Array> {
+        cast(key, self.try_get(key)?)
     }
 
-    pub fn get_str_array(&self, key: &str) -> Option<Vec<String>> {
-        return match self.get_array(key) {
-            None => None,
-            Some(a) => {
-                let mut str_vec: Vec<String> = Vec::new();
-                for val in a {
-                    if val.is::<String>() != true {
-                        // TODO: Should this occur?
-                        return None;
-                    }
-
-                    str_vec.push(val.into_string().unwrap())
-                }
-
-                Some(str_vec)
+    pub fn try_get_str_array(&self, key: &str) -> ConfigResult<Vec<String>> {
+        let mut str_vec: Vec<String> = Vec::new();
+        for (index, val) in self.try_get_array(key)?.into_iter().enumerate() {
+            if val.is::<String>() != true {
+                return Err(ConfigError::BadElement {
+                    path: key.to_string(),
+                    index,
+                    expected: type_name::<String>(),
+                    found: val.type_name().to_string(),
+                });
             }
+
+            str_vec.push(val.into_string().unwrap())
         }
+
+        Ok(str_vec)
     }
 
-    fn _get(&self, key: &str) -> Dynamic {
-        if key.is_empty() {
-            return Dynamic::UNIT;
-        }
+    /// Set the value at `key`, creating any intermediate maps (array elements must exist).
+    pub fn set(&mut self, key: &str, value: impl Into<Dynamic>) -> ConfigResult<()> {
+        let segments = parse_path(key)?;
+        let key = format_path(&segments);
 
-        let keys = key.split(".").collect::<Vec<_>>();
+        let root = self.data.entry(segments[0].as_key().into())
+            .or_insert_with(|| Map::new().into());
+        set_in(root, &segments, 1, value.into())?;
 
-        // Get the first key (outside the loop as this comes from data).
-        let cur_key = match keys.get(0) {
-            None => {
-                return Dynamic::UNIT;
-            }
-            Some(cur_key) => cur_key
-        };
+        // Invalidate the key, anything beneath it and any parent containing it.
+        self.cache.borrow_mut().retain(|k, _| {
+            !(*k == key
+                || (k.starts_with(&key) && k[key.len()..].starts_with('.'))
+                || (key.starts_with(k.as_str()) && key[k.len()..].starts_with('.')))
+        });
+
+        Ok(())
+    }
 
-        let mut cur = match self.data.get(*cur_key) {
+    fn _get(&self, segments: &[PathSegment]) -> ConfigResult<Dynamic> {
+        // Get the first key (outside the loop as this comes from data).
+        let mut cur = match self.data.get(segments[0].as_key().as_str()) {
             None => {
-                return Dynamic::UNIT;
+                return Err(ConfigError::Missing(format_path(&segments[..1])));
             }
             Some(cur) => cur.clone()
         };
 
-        if keys.len() >= 2 {
-            for ki in 1..keys.len() {
-                // Is cur a Map?
-                if cur.is::<Map>() != true {
-                    return Dynamic::UNIT;
-                }
-
-                let cur_map = cur.read_lock::<Map>().unwrap();
-
-                // Get the next key value
-                let cur_key = match keys.get(ki) {
-                    None => {
-                        return Dynamic::UNIT;
-                    }
-                    Some(cur_key) => cur_key
-                };
-
-                cur = match cur_map.get(*cur_key) {
-                    None => {
-                        return Dynamic::UNIT;
-                    }
-                    Some(cur) => cur.clone()
-                };
-            }
+        for si in 1..segments.len() {
+            let next = if cur.is::<Map>() {
+                cur.read_lock::<Map>().unwrap()
+                    .get(segments[si].as_key().as_str())
+                    .cloned()
+            } else if cur.is::<Array>() {
+                let index = segments[si].as_index().ok_or_else(|| ConfigError::TypeMismatch {
+                    path: format_path(&segments[..si]),
+                    expected: type_name::<Map>(),
+                    found: cur.type_name().to_string(),
+                })?;
+
+                cur.read_lock::<Array>().unwrap().get(index).cloned()
+            } else {
+                return Err(ConfigError::TypeMismatch {
+                    path: format_path(&segments[..si]),
+                    expected: format!("{} or {}", type_name::<Map>(), type_name::<Array>()),
+                    found: cur.type_name().to_string(),
+                });
+            };
+
+            cur = next.ok_or_else(|| ConfigError::Missing(format_path(&segments[..=si])))?;
         }
 
-        return cur;
+        return Ok(cur);
+    }
+}
+
+fn set_in(cur: &mut Dynamic, segments: &[PathSegment], si: usize, value: Dynamic) -> ConfigResult<()> {
+    if si == segments.len() {
+        *cur = value;
+        return Ok(());
+    }
+
+    if cur.is_unit() {
+        *cur = Map::new().into();
+    }
+
+    let found = cur.type_name().to_string();
+
+    if let Some(mut map) = cur.write_lock::<Map>() {
+        let child = map.entry(segments[si].as_key().into())
+            .or_insert_with(|| Map::new().into());
+        return set_in(child, segments, si + 1, value);
+    }
+
+    if let Some(mut arr) = cur.write_lock::<Array>() {
+        let index = segments[si].as_index().ok_or_else(|| ConfigError::TypeMismatch {
+            path: format_path(&segments[..si]),
+            expected: type_name::<Map>(),
+            found: found.clone(),
+        })?;
+
+        return match arr.get_mut(index) {
+            Some(child) => set_in(child, segments, si + 1, value),
+            None => Err(ConfigError::Missing(format_path(&segments[..=si]))),
+        };
+    }
+
+    Err(ConfigError::TypeMismatch {
+        path: format_path(&segments[..si]),
+        expected: format!("{} or {}", type_name::<Map>(), type_name::<Array>()),
+        found,
+    })
+}
+
+fn cast<T: Variant + Clone>(key: &str, val: Dynamic) -> ConfigResult<T> {
+    let found = val.type_name().to_string();
+
+    val.try_cast::<T>().ok_or_else(|| ConfigError::TypeMismatch {
+        path: key.to_string(),
+        expected: type_name::<T>(),
+        found,
+    })
+}
+
+/// The name of `T` as reported by `Dynamic::type_name`.
+fn type_name<T: Variant>() -> String {
+    let id = TypeId::of::<T>();
+    let name = match id {
+        _ if id == TypeId::of::<String>() => "string",
+        _ if id == TypeId::of::<INT>() => std::any::type_name::<INT>(),
+        _ if id == TypeId::of::<bool>() => "bool",
+        _ if id == TypeId::of::<Array>() => "array",
+        _ if id == TypeId::of::<Map>() => "map",
+        _ => std::any::type_name::<T>(),
+    };
+
+    name.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use rhai::Engine;
+
+    use super::*;
+
+    fn config() -> Config {
+        let map = Engine::new().parse_json(r#"{
+            "name": "cortex",
+            "limits": { "max": 10, "enabled": true },
+            "tags": ["a", "b"],
+            "mixed": ["a", 1],
+            "tiers": [{ "rate": 1 }, { "rate": 2 }]
+        }"#, false).unwrap();
+
+        Config::new(map)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn try_get_works() {
+        let config = config();
+
+        assert_eq!(config.try_get_str("name").unwrap(), "cortex");
+        assert_eq!(config.try_get_int("limits.max").unwrap(), 10);
+        assert!(config.try_get_bool("limits.enabled").unwrap());
+        assert_eq!(config.try_get_array("tags").unwrap().len(), 2);
+        assert_eq!(config.try_get_str_array("tags").unwrap(), vec!["a", "b"]);
+        assert_eq!(config.try_get_int("tiers[1].rate").unwrap(), 2);
+        assert_eq!(config.try_get_int("tiers.0.rate").unwrap(), 1);
+
+        assert_eq!(config.try_get("limits.min").unwrap_err(), ConfigError::Missing("limits.min".to_string()));
+        assert_eq!(config.try_get("tiers[2].rate").unwrap_err(), ConfigError::Missing("tiers.2".to_string()));
+        assert!(config.get("nope").is_unit());
+        assert_eq!(config.get_int("name"), None);
+    }
+
+    #[test]
+    fn try_get_reports_type_mismatches() {
+        let config = config();
+
+        assert_eq!(config.try_get_int("name"), Err(ConfigError::TypeMismatch {
+            path: "name".to_string(),
+            expected: type_name::<INT>(),
+            found: "string".to_string(),
+        }));
+        assert_eq!(config.try_get_array("limits").unwrap_err(), ConfigError::TypeMismatch {
+            path: "limits".to_string(),
+            expected: "array".to_string(),
+            found: "map".to_string(),
+        });
+        assert_eq!(config.try_get("name.first").unwrap_err(), ConfigError::TypeMismatch {
+            path: "name".to_string(),
+            expected: "map or array".to_string(),
+            found: "string".to_string(),
+        });
+        assert_eq!(config.try_get("tags.first").unwrap_err(), ConfigError::TypeMismatch {
+            path: "tags".to_string(),
+            expected: "map".to_string(),
+            found: "array".to_string(),
+        });
+    }
+
+    #[test]
+    fn try_get_str_array_reports_bad_elements() {
+        let config = config();
+
+        assert_eq!(config.try_get_str_array("mixed"), Err(ConfigError::BadElement {
+            path: "mixed".to_string(),
+            index: 1,
+            expected: "string".to_string(),
+            found: type_name::<INT>(),
+        }));
+        assert_eq!(config.get_str_array("mixed"), None);
+        assert!(matches!(config.try_get_str_array("name"), Err(ConfigError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn get_or_works() {
+        let config = config();
+
+        assert_eq!(config.get_or("limits.max", 5 as INT).unwrap(), 10);
+        assert_eq!(config.get_or("limits.min", 5 as INT).unwrap(), 5);
+        assert_eq!(config.get_or("limits.max", false), Err(ConfigError::TypeMismatch {
+            path: "limits.max".to_string(),
+            expected: "bool".to_string(),
+            found: type_name::<INT>(),
+        }));
+        assert!(matches!(config.get_or("a..b", 5 as INT), Err(ConfigError::InvalidPath { .. })));
+    }
+
+    #[test]
+    fn set_on_parent_invalidates_cached_child() {
+        let mut config = config();
+        assert_eq!(config.try_get_int("limits.max").unwrap(), 10);
+        assert_eq!(config.try_get_int("tiers[0].rate").unwrap(), 1);
+
+        let mut limits = Map::new();
+        limits.insert("max".into(), (20 as INT).into());
+        config.set("limits", limits).unwrap();
+        config.set("tiers", Array::new()).unwrap();
+
+        assert_eq!(config.try_get_int("limits.max").unwrap(), 20);
+        assert_eq!(config.try_get("limits.enabled").unwrap_err(), ConfigError::Missing("limits.enabled".to_string()));
+        assert_eq!(config.try_get("tiers[0].rate").unwrap_err(), ConfigError::Missing("tiers.0".to_string()));
+    }
+
+    #[test]
+    fn set_on_child_invalidates_cached_parent() {
+        let mut config = config();
+        assert_eq!(config.try_get("limits").unwrap().read_lock::<Map>().unwrap().len(), 2);
+        assert_eq!(config.try_get_int("tiers.1.rate").unwrap(), 2);
+
+        config.set("limits.min", 1 as INT).unwrap();
+        config.set("tiers[1].rate", 3 as INT).unwrap();
+
+        let limits = config.try_get("limits").unwrap();
+        assert_eq!(limits.read_lock::<Map>().unwrap().get("min").unwrap().as_int().unwrap(), 1);
+        assert_eq!(config.try_get_int("tiers.1.rate").unwrap(), 3);
+        let tiers = config.try_get_array("tiers").unwrap();
+        assert_eq!(tiers[1].read_lock::<Map>().unwrap().get("rate").unwrap().as_int().unwrap(), 3);
+    }
+
+    #[test]
+    fn set_creates_maps_but_not_array_elements() {
+        let mut config = config();
+
+        config.set("new.nested.key", "value".to_string()).unwrap();
+        assert_eq!(config.try_get_str("new.nested.key").unwrap(), "value");
+
+        assert_eq!(config.set("tiers[5].rate", 1 as INT), Err(ConfigError::Missing("tiers.5".to_string())));
+        assert_eq!(config.set("name.first", 1 as INT), Err(ConfigError::TypeMismatch {
+            path: "name".to_string(),
+            expected: "map or array".to_string(),
+            found: "string".to_string(),
+        }));
+    }
+}
@@ -0,0 +1,257 @@
+use std::convert::TryFrom;
+
+use rhai::{Array, Dynamic, INT, Map};
+
+use crate::result::{ResolverError, ResolverResult};
+
+/// Convert a TOML document into the same `Map` a JSON config produces.
+#[cfg(feature = "toml_config")]
+pub fn toml_to_map(source: &str) -> ResolverResult<Map> {
+    let value = source.parse::<toml::Value>().map_err(|err| {
+        ResolverError::ConfigParseFailed(err.to_string())
+    })?;
+
+    return match toml_to_dynamic(value, "")?.try_cast::<Map>() {
+        Some(map) => Ok(map),
+        None => Err(ResolverError::ConfigParseFailed("config root must be a table".to_string()))
+    };
+}
+
+#[cfg(feature = "toml_config")]
+fn toml_to_dynamic(value: toml::Value, path: &str) -> ResolverResult<Dynamic> {
+    use toml::Value;
+
+    return match value {
+        Value::String(s) => Ok(s.into()),
+        Value::Integer(i) => Ok(int_from_i64(i, path)?.into()),
+        Value::Boolean(b) => Ok(b.into()),
+        Value::Datetime(d) => Ok(d.to_string().into()),
+        Value::Float(_) => Err(unsupported("float", path)),
+        Value::Array(values) => {
+            let mut arr = Array::new();
+            for (i, v) in values.into_iter().enumerate() {
+                arr.push(toml_to_dynamic(v, &format!("{path}[{i}]"))?);
+            }
+
+            Ok(arr.into())
+        }
+        Value::Table(table) => {
+            let mut map = Map::new();
+            for (k, v) in table {
+                let child = join_path(path, &k);
+                map.insert(k.into(), toml_to_dynamic(v, &child)?);
+            }
+
+            Ok(map.into())
+        }
+    };
+}
+
+/// Convert a YAML document into the same `Map` a JSON config produces.
+#[cfg(feature = "yaml_config")]
+pub fn yaml_to_map(source: &str) -> ResolverResult<Map> {
+    let value = serde_yaml::from_str::<serde_yaml::Value>(source).map_err(|err| {
+        ResolverError::ConfigParseFailed(err.to_string())
+    })?;
+
+    return match yaml_to_dynamic(value, "")?.try_cast::<Map>() {
+        Some(map) => Ok(map),
+        None => Err(ResolverError::ConfigParseFailed("config root must be a mapping".to_string()))
+    };
+}
+
+#[cfg(feature = "yaml_config")]
+fn yaml_to_dynamic(value: serde_yaml::Value, path: &str) -> ResolverResult<Dynamic> {
+    use serde_yaml::Value;
+
+    return match value {
+        Value::Null => Ok(Dynamic::UNIT),
+        Value::Bool(b) => Ok(b.into()),
+        Value::String(s) => Ok(s.into()),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(int_from_i64(i, path)?.into()),
+            None => Err(unsupported("non-integer number", path)),
+        },
+        Value::Sequence(values) => {
+            let mut arr = Array::new();
+            for (i, v) in values.into_iter().enumerate() {
+                arr.push(yaml_to_dynamic(v, &format!("{path}[{i}]"))?);
+            }
+
+            Ok(arr.into())
+        }
+        Value::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (k, v) in mapping {
+                let k = match k {
+                    Value::String(k) => k,
+                    _ => return Err(unsupported("non-string key", path)),
+                };
+                let child = join_path(path, &k);
+                map.insert(k.into(), yaml_to_dynamic(v, &child)?);
+            }
+
+            Ok(map.into())
+        }
+        Value::Tagged(tagged) => yaml_to_dynamic(tagged.value, path),
+    };
+}
+
+fn int_from_i64(i: i64, path: &str) -> ResolverResult<INT> {
+    INT::try_from(i).map_err(|_| {
+        ResolverError::ConfigParseFailed(format!("integer at '{path}' is out of range"))
+    })
+}
+
+#[inline(always)]
+fn unsupported(what: &str, path: &str) -> ResolverError {
+    ResolverError::ConfigParseFailed(format!("{what} at '{path}' is not supported"))
+}
+
+#[inline(always)]
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        return key.to_string();
+    }
+
+    format!("{path}.{key}")
+}
+
+#[cfg(test)]
+mod test {
+    use rhai::Engine;
+
+    use super::*;
+
+    const JSON: &str = r#"{
+        "name": "cortex",
+        "enabled": true,
+        "unset": null,
+        "limits": { "max": 10, "min": -2 },
+        "tags": ["a", "b"],
+        "tiers": [{ "rate": 1 }, { "rate": 2 }]
+    }"#;
+
+    fn json_map() -> Map {
+        Engine::new().parse_json(JSON, true).unwrap()
+    }
+
+    fn assert_same(map: Map, expected: Map) {
+        assert_eq!(format!("{map:?}"), format!("{expected:?}"));
+    }
+
+    fn assert_parse_failed(res: ResolverResult<Map>, expected: &str) {
+        match res {
+            Err(ResolverError::ConfigParseFailed(msg)) => assert_eq!(msg, expected),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "toml_config")]
+    fn toml_matches_json() {
+        let map = toml_to_map(r#"
+            name = "cortex"
+            enabled = true
+            tags = ["a", "b"]
+
+            [limits]
+            max = 10
+            min = -2
+
+            [[tiers]]
+            rate = 1
+
+            [[tiers]]
+            rate = 2
+        "#).unwrap();
+
+        // TOML has no null.
+        let mut expected = json_map();
+        expected.remove("unset");
+        assert_same(map, expected);
+
+        let map = toml_to_map("at = 1979-05-27T07:32:00Z").unwrap();
+        assert_eq!(map["at"].clone().into_string().unwrap(), "1979-05-27T07:32:00Z");
+    }
+
+    #[test]
+    #[cfg(feature = "toml_config")]
+    fn toml_rejects_floats() {
+        assert_parse_failed(toml_to_map("[limits]\nrate = 1.5"), "float at 'limits.rate' is not supported");
+        assert_parse_failed(toml_to_map("rates = [1.5]"), "float at 'rates[0]' is not supported");
+        assert!(matches!(toml_to_map("name = "), Err(ResolverError::ConfigParseFailed(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "yaml_config")]
+    fn yaml_matches_json() {
+        let map = yaml_to_map(r#"
+name: cortex
+enabled: true
+unset: null
+limits:
+  max: 10
+  min: -2
+tags: [a, b]
+tiers:
+  - rate: 1
+  - rate: 2
+"#).unwrap();
+
+        assert_same(map, json_map());
+    }
+
+    #[test]
+    #[cfg(feature = "yaml_config")]
+    fn yaml_rejects_floats_and_non_string_keys() {
+        assert_parse_failed(yaml_to_map("limits:\n  rate: 1.5"), "non-integer number at 'limits.rate' is not supported");
+        assert_parse_failed(yaml_to_map("rates:\n  - 1.5"), "non-integer number at 'rates[0]' is not supported");
+        assert_parse_failed(yaml_to_map("limits:\n  1: one"), "non-string key at 'limits' is not supported");
+        assert_parse_failed(yaml_to_map("- a\n- b"), "config root must be a mapping");
+    }
+
+    #[test]
+    #[cfg(all(feature = "zip_archive", feature = "json_config", feature = "toml_config", feature = "yaml_config"))]
+    fn multiple_config_formats_are_rejected() {
+        use std::io::{Cursor, Write};
+
+        use zip::write::FileOptions;
+        use zip::ZipWriter;
+
+        use crate::ZipModuleResolver;
+
+        let resolver = |files: &[(&str, &str)]| {
+            let mut buf = Cursor::new(Vec::new());
+            {
+                let mut zip = ZipWriter::new(&mut buf);
+                for (name, content) in files {
+                    zip.start_file(*name, FileOptions::default()).unwrap();
+                    zip.write_all(content.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+
+            let mut resolver = ZipModuleResolver::new();
+            resolver.load_from_bytes(buf.into_inner()).unwrap();
+            resolver
+        };
+
+        let mut single = resolver(&[("config.toml", "name = \"cortex\""), ("main.rhai", "fn main() {}")]);
+        assert_eq!(single.config_extension().unwrap(), "toml");
+        single.load_config(&Engine::new()).unwrap();
+        assert_eq!(single.config().get_str("name").unwrap(), "cortex");
+
+        let multiple = resolver(&[("config.json", "{}"), ("config.yaml", "name: cortex"),
+                                  ("main.rhai", "fn main() {}")]);
+        match multiple.config_extension() {
+            Err(ResolverError::MultipleConfigs(files)) => {
+                assert_eq!(files, vec!["config.json".to_string(), "config.yaml".to_string()]);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        let none = resolver(&[("main.rhai", "fn main() {}")]);
+        assert!(matches!(none.config_extension(), Err(ResolverError::FileNotFound)));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use rhai::{AST, ASTNode, Expr, Stmt};
+
+/// The static import graph of a bundle, built from the entrypoints.
+#[derive(Debug, Clone, Default)]
+pub struct ImportGraph {
+    /// The entrypoints the graph was built from.
+    pub roots: Vec<PathBuf>,
+    /// Each reachable file and the files it imports.
+    pub edges: BTreeMap<PathBuf, Vec<PathBuf>>,
+    /// Imports which don't exist in the archive: (importing file, import path).
+    pub missing: Vec<(PathBuf, String)>,
+    /// Import cycles, each listed from the first file back to itself.
+    pub cycles: Vec<Vec<PathBuf>>,
+    /// Script files in the archive not reachable from any entrypoint.
+    pub unreachable: Vec<PathBuf>,
+    /// Files which import a non-literal path (and so can't be checked).
+    pub dynamic: Vec<PathBuf>,
+    /// Reachable files with every file before the files importing it.
+    pub order: Vec<PathBuf>,
+}
+
+impl ImportGraph {
+    /// No missing imports and no cycles.
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.cycles.is_empty()
+    }
+
+    /// Describe every problem found (missing imports and cycles).
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (file, import) in &self.missing {
+            errors.push(format!("'{}' imports missing module '{}'", file.display(), import));
+        }
+        for cycle in &self.cycles {
+            let files = cycle.iter()
+                .map(|f| f.display().to_string())
+                .collect::<Vec<_>>();
+            errors.push(format!("import cycle: {}", files.join(" -> ")));
+        }
+
+        errors
+    }
+}
+
+/// Collect the literal import paths from an AST (`None` for any dynamic path).
+pub fn collect_imports(ast: &AST) -> Vec<Option<String>> {
+    let mut imports = Vec::new();
+
+    ast.walk(&mut |path: &[ASTNode]| {
+        if let Some(ASTNode::Stmt(Stmt::Import(x, ..))) = path.last() {
+            imports.push(match &x.0 {
+                Expr::StringConstant(s, ..) => Some(s.to_string()),
+                _ => None,
+            });
+        }
+
+        true
+    });
+
+    imports
+}
@@ -1,9 +1,36 @@
+mod archive;
+mod cache;
+mod graph;
+mod path;
+#[cfg(feature = "baked_consts")]
+mod baked;
 mod resolver;
 mod result;
-#[cfg(feature = "json_config")]
+mod source_map;
+#[cfg(feature = "config")]
 mod config;
+#[cfg(any(feature = "toml_config", feature = "yaml_config"))]
+mod formats;
 
-pub use resolver::{ZipModuleResolver, RHAI_EXTENSION};
-pub use result::{ResolverResult, ResolverError};
-#[cfg(feature = "json_config")]
+pub use archive::{Archive, open_archive};
+#[cfg(feature = "container_archive")]
+pub use archive::{ContainerArchive, write_container};
+#[cfg(feature = "tar_archive")]
+pub use archive::TarArchive;
+#[cfg(feature = "zip_archive")]
+pub use archive::ZipFileArchive;
+pub use cache::{bundle_hash, BundleHash, ModuleCacheStats, SharedModuleCache};
+pub use graph::ImportGraph;
+#[cfg(feature = "baked_consts")]
+pub use baked::{load_manifest, BakedConsts, BAKED_CONSTS_FORMAT, BAKED_CONSTS_MANIFEST};
+#[cfg(feature = "bake_consts")]
+pub use baked::bake_consts;
+pub use path::{canonical_path, format_path, parse_path, PathSegment};
+pub use resolver::{normalize_path, ZipModuleResolver, DEFAULT_MAX_FILE_SIZE, RHAI_EXTENSION};
+#[cfg(feature = "config")]
+pub use resolver::{CFG_KEY_ASSETS_DIR, CFG_KEY_ASSETS_MAX_SIZE, CFG_KEY_ENTRYPOINT_GROUPS,
+                   CFG_KEY_GLOBAL_CONSTANTS, CFG_KEY_GLOBAL_ENTRYPOINTS};
+pub use result::{ConfigError, ConfigResult, ResolverResult, ResolverError};
+pub use source_map::SourceMap;
+#[cfg(feature = "config")]
 pub use config::Config;
\ No newline at end of file
@@ -0,0 +1,246 @@
+use crate::result::{ConfigError, ConfigResult};
+
+/// A single segment of a dotted path.
+///
+/// Paths are segments separated by `.`, where a segment is either a bare key (`\` escapes
+/// the next character), a quoted key (`"a.b"`, with `\"` and `\\` escapes) or an array
+/// index (`tiers[0]`). A bare numeric key (`tiers.0`) also indexes into arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl PathSegment {
+    /// The segment as an array index, if it is one (or a numeric key).
+    pub fn as_index(&self) -> Option<usize> {
+        return match self {
+            PathSegment::Index(i) => Some(*i),
+            PathSegment::Key(k) => k.parse::<usize>().ok(),
+        };
+    }
+
+    /// The segment as a map key.
+    pub fn as_key(&self) -> String {
+        return match self {
+            PathSegment::Index(i) => i.to_string(),
+            PathSegment::Key(k) => k.clone(),
+        };
+    }
+}
+
+pub fn parse_path(path: &str) -> ConfigResult<Vec<PathSegment>> {
+    let err = |reason: String| ConfigError::InvalidPath {
+        path: path.to_string(),
+        reason,
+    };
+
+    if path.is_empty() {
+        return Err(err("path is empty".to_string()));
+    }
+
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+
+    loop {
+        let mut key = String::new();
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    None => return Err(err("unterminated quoted key".to_string())),
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c) => key.push(c),
+                        None => return Err(err("trailing escape".to_string())),
+                    },
+                    Some(c) => key.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.peek().copied() {
+                match c {
+                    '.' | '[' => break,
+                    ']' | '"' => return Err(err(format!("unexpected '{c}'"))),
+                    '\\' => {
+                        chars.next();
+                        match chars.next() {
+                            Some(c) => key.push(c),
+                            None => return Err(err("trailing escape".to_string())),
+                        }
+                    }
+                    _ => {
+                        chars.next();
+                        key.push(c);
+                    }
+                }
+            }
+
+            if key.is_empty() {
+                return Err(err("empty key".to_string()));
+            }
+        }
+
+        segments.push(PathSegment::Key(key));
+
+        while chars.peek() == Some(&'[') {
+            chars.next();
+
+            let mut digits = String::new();
+            loop {
+                match chars.next() {
+                    None => return Err(err("unterminated index".to_string())),
+                    Some(']') => break,
+                    Some(c) => digits.push(c),
+                }
+            }
+
+            let index = digits.parse::<usize>()
+                .map_err(|_| err(format!("invalid index '{digits}'")))?;
+            segments.push(PathSegment::Index(index));
+        }
+
+        match chars.next() {
+            None => break,
+            Some('.') => continue,
+            Some(c) => return Err(err(format!("unexpected '{c}'"))),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Format segments in their canonical form (indices are rendered as bare numeric keys,
+/// so `tiers[0]` and `tiers.0` format the same).
+pub fn format_path(segments: &[PathSegment]) -> String {
+    let mut path = String::new();
+
+    for segment in segments {
+        if !path.is_empty() {
+            path.push('.');
+        }
+
+        match segment {
+            PathSegment::Index(i) => path.push_str(&i.to_string()),
+            PathSegment::Key(k) => {
+                let needs_quotes = k.is_empty()
+                    || k.chars().any(|c| matches!(c, '.' | '[' | ']' | '"' | '\\'));
+                if !needs_quotes {
+                    path.push_str(k);
+                    continue;
+                }
+
+                path.push('"');
+                for c in k.chars() {
+                    if c == '"' || c == '\\' {
+                        path.push('\\');
+                    }
+                    path.push(c);
+                }
+                path.push('"');
+            }
+        }
+    }
+
+    path
+}
+
+/// Parse then format a path, i.e. `a[0]."b"` becomes `a.0.b`.
+#[inline(always)]
+pub fn canonical_path(path: &str) -> ConfigResult<String> {
+    Ok(format_path(&parse_path(path)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::PathSegment::{Index, Key};
+
+    fn key(k: &str) -> PathSegment {
+        Key(k.to_string())
+    }
+
+    fn reason(path: &str) -> String {
+        return match parse_path(path) {
+            Err(ConfigError::InvalidPath { path: p, reason }) => {
+                assert_eq!(p, path);
+                reason
+            }
+            other => panic!("expected '{path}' to be invalid, got {other:?}"),
+        };
+    }
+
+    #[test]
+    fn parse_path_works() {
+        assert_eq!(parse_path("a").unwrap(), vec![key("a")]);
+        assert_eq!(parse_path("a.b.c").unwrap(), vec![key("a"), key("b"), key("c")]);
+        assert_eq!(parse_path("tiers.0").unwrap(), vec![key("tiers"), key("0")]);
+        assert_eq!(parse_path("tiers[0]").unwrap(), vec![key("tiers"), Index(0)]);
+        assert_eq!(parse_path("grid[1][22].x").unwrap(), vec![key("grid"), Index(1), Index(22), key("x")]);
+    }
+
+    #[test]
+    fn parse_path_quoted_and_escaped_keys() {
+        assert_eq!(parse_path(r#""a.b".c"#).unwrap(), vec![key("a.b"), key("c")]);
+        assert_eq!(parse_path(r#"a."b[0]"[1]"#).unwrap(), vec![key("a"), key("b[0]"), Index(1)]);
+        assert_eq!(parse_path(r#""say \"hi\"".x"#).unwrap(), vec![key(r#"say "hi""#), key("x")]);
+        assert_eq!(parse_path(r#""back\\slash""#).unwrap(), vec![key(r"back\slash")]);
+        assert_eq!(parse_path(r"a\.b.c").unwrap(), vec![key("a.b"), key("c")]);
+        assert_eq!(parse_path(r"a\[0\]").unwrap(), vec![key("a[0]")]);
+        assert_eq!(parse_path(r#"a."""#).unwrap(), vec![key("a"), key("")]);
+    }
+
+    #[test]
+    fn parse_path_rejects_invalid_paths() {
+        assert_eq!(reason(""), "path is empty");
+        assert_eq!(reason(".a"), "empty key");
+        assert_eq!(reason("a..b"), "empty key");
+        assert_eq!(reason("a."), "empty key");
+        assert_eq!(reason("[0]"), "empty key");
+        assert_eq!(reason(r#""a"#), "unterminated quoted key");
+        assert_eq!(reason(r#""a\"#), "trailing escape");
+        assert_eq!(reason(r"a\"), "trailing escape");
+        assert_eq!(reason("a[0"), "unterminated index");
+        assert_eq!(reason("a[x]"), "invalid index 'x'");
+        assert_eq!(reason("a[-1]"), "invalid index '-1'");
+        assert_eq!(reason("a]"), "unexpected ']'");
+        assert_eq!(reason(r#"a"b""#), "unexpected '\"'");
+        assert_eq!(reason(r#""a"b"#), "unexpected 'b'");
+    }
+
+    #[test]
+    fn format_path_works() {
+        assert_eq!(format_path(&[key("a"), key("b")]), "a.b");
+        assert_eq!(format_path(&[key("tiers"), Index(0), key("rate")]), "tiers.0.rate");
+        assert_eq!(format_path(&[key("a.b"), key(""), key(r#"say "hi""#), key(r"x\y"), key("[0]")]),
+                   r##""a.b".""."say \"hi\""."x\\y"."[0]""##);
+    }
+
+    #[test]
+    fn canonical_path_works() {
+        assert_eq!(canonical_path("tiers[0].rate").unwrap(), "tiers.0.rate");
+        assert_eq!(canonical_path(r#""tiers"."0".rate"#).unwrap(), "tiers.0.rate");
+        assert_eq!(canonical_path(r"a\.b").unwrap(), r#""a.b""#);
+        assert!(canonical_path("a..b").is_err());
+    }
+
+    #[test]
+    fn parse_inverts_format() {
+        let paths = vec![
+            vec![key("a")],
+            vec![key("a"), key("b"), key("0")],
+            vec![key("a.b"), key(""), key(r#"say "hi""#), key(r"x\y"), key("[0]"), key("]")],
+            vec![key("unicode ü"), key("with space")],
+        ];
+        for path in paths {
+            assert_eq!(parse_path(&format_path(&path)).unwrap(), path);
+        }
+
+        // Indexes are formatted as numeric keys, which index arrays the same.
+        let parsed = parse_path(&format_path(&[key("tiers"), Index(3)])).unwrap();
+        assert_eq!(parsed, vec![key("tiers"), key("3")]);
+        assert_eq!(parsed[1].as_index(), Some(3));
+        assert_eq!(Index(3).as_key(), "3");
+    }
+}
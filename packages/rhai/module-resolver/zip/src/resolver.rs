@@ -1,39 +1,81 @@
 use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::io::{Cursor, Read};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str;
 
 use cfg_if::cfg_if;
-use rhai::{AST, Engine, EvalAltResult, GlobalRuntimeState, Locked, Map, Module, ModuleResolver, Position, Scope, Shared};
-use zip::ZipArchive;
+use rhai::{AST, ASTFlags, Dynamic, Engine, EvalAltResult, GlobalRuntimeState, Locked, Map, Module, ModuleResolver, Position, Scope, Shared, Stmt};
 
-#[cfg(feature = "json_config")]
+#[cfg(feature = "config")]
 use crate::config::Config;
+#[cfg(feature = "toml_config")]
+use crate::formats::toml_to_map;
+#[cfg(feature = "yaml_config")]
+use crate::formats::yaml_to_map;
+#[cfg(feature = "config")]
+use crate::result::ConfigError;
+use crate::archive::{Archive, open_archive};
+use crate::cache::{bundle_hash, BundleHash, CompiledModule, SharedModuleCache};
+#[cfg(feature = "baked_consts")]
+use crate::baked::{load_manifest, BakedConsts, BAKED_CONSTS_MANIFEST};
+use crate::graph::{collect_imports, ImportGraph};
 use crate::result::{map_resolver_err_to_eval_err, ResolverError, ResolverResult};
+use crate::source_map::SourceMap;
 
 pub const RHAI_EXTENSION: &'static str = "rhai";
-#[cfg(feature = "json_config")]
 pub const JSON_EXTENSION: &'static str = "json";
+#[cfg(feature = "toml_config")]
+pub const TOML_EXTENSION: &'static str = "toml";
+#[cfg(feature = "yaml_config")]
+pub const YAML_EXTENSION: &'static str = "yaml";
 
-#[cfg(feature = "json_config")]
+#[cfg(feature = "config")]
 pub const CFG_FILE: &'static str = "config";
 
-#[cfg(feature = "json_config")]
+#[cfg(feature = "config")]
 pub const CFG_KEY_GLOBAL_ENTRYPOINTS: &'static str = "global.entrypoints";
 
+/// Constants (name: value) visible to every script.
+#[cfg(feature = "config")]
+pub const CFG_KEY_GLOBAL_CONSTANTS: &'static str = "global.constants";
+
+#[cfg(feature = "config")]
+pub const CFG_KEY_ENTRYPOINT_GROUPS: &'static str = "entrypoints";
+
+/// The directory (within the archive) scripts may read assets from.
+#[cfg(feature = "config")]
+pub const CFG_KEY_ASSETS_DIR: &'static str = "assets.dir";
+/// An optional, lower, size limit for assets.
+#[cfg(feature = "config")]
+pub const CFG_KEY_ASSETS_MAX_SIZE: &'static str = "assets.max_size";
+
+/// The largest (uncompressed) file the resolver will read.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
 
 // Define a custom module resolver.
 #[derive(Debug, Clone)]
 pub struct ZipModuleResolver {
-    zip: RefCell<Option<ZipArchive<Cursor<Vec<u8>>>>>,
+    archive: RefCell<Option<Box<dyn Archive>>>,
+    bundle_hash: Option<BundleHash>,
+    shared_cache: Option<SharedModuleCache>,
+    #[cfg(feature = "baked_consts")]
+    baked_consts: Option<Rc<BakedConsts>>,
     scope: Scope<'static>,
-    #[cfg(feature = "json_config")]
+    scope_key: Vec<u8>,
+    #[cfg(feature = "config")]
     config: Option<Config>,
+    #[cfg(feature = "config")]
+    entrypoint_group: Option<String>,
+    /// The entrypoint file defining each entrypoint function (to remap runtime errors).
+    #[cfg(feature = "config")]
+    entrypoint_fns: BTreeMap<String, PathBuf>,
     base_path: Option<PathBuf>,
     extension: String,
     cache_enabled: bool,
+    max_file_size: u64,
 
     #[cfg(not(feature = "sync"))]
     cache: RefCell<BTreeMap<PathBuf, Shared<Module>>>,
@@ -52,13 +94,23 @@ impl ZipModuleResolver {
     #[must_use]
     pub fn new_with_scope(scope: Scope<'static>) -> Self {
         Self {
-            zip: RefCell::new(None),
+            archive: RefCell::new(None),
+            bundle_hash: None,
+            shared_cache: None,
+            #[cfg(feature = "baked_consts")]
+            baked_consts: None,
             scope,
-            #[cfg(feature = "json_config")]
+            scope_key: Vec::new(),
+            #[cfg(feature = "config")]
             config: None,
+            #[cfg(feature = "config")]
+            entrypoint_group: None,
+            #[cfg(feature = "config")]
+            entrypoint_fns: BTreeMap::new(),
             base_path: None,
             extension: RHAI_EXTENSION.to_string(),
             cache_enabled: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
             cache: BTreeMap::new().into(),
         }
     }
@@ -67,13 +119,23 @@ impl ZipModuleResolver {
     #[must_use]
     pub fn new_with_extension(extension: String) -> Self {
         Self {
-            zip: RefCell::new(None),
+            archive: RefCell::new(None),
+            bundle_hash: None,
+            shared_cache: None,
+            #[cfg(feature = "baked_consts")]
+            baked_consts: None,
             scope: Scope::new(),
-            #[cfg(feature = "json_config")]
+            scope_key: Vec::new(),
+            #[cfg(feature = "config")]
             config: None,
+            #[cfg(feature = "config")]
+            entrypoint_group: None,
+            #[cfg(feature = "config")]
+            entrypoint_fns: BTreeMap::new(),
             base_path: None,
             extension: extension,
             cache_enabled: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
             cache: BTreeMap::new().into(),
         }
     }
@@ -85,13 +147,23 @@ impl ZipModuleResolver {
         extension: String,
     ) -> Self {
         Self {
-            zip: RefCell::new(None),
+            archive: RefCell::new(None),
+            bundle_hash: None,
+            shared_cache: None,
+            #[cfg(feature = "baked_consts")]
+            baked_consts: None,
             scope: Scope::new(),
-            #[cfg(feature = "json_config")]
+            scope_key: Vec::new(),
+            #[cfg(feature = "config")]
             config: None,
+            #[cfg(feature = "config")]
+            entrypoint_group: None,
+            #[cfg(feature = "config")]
+            entrypoint_fns: BTreeMap::new(),
             base_path: Some(path.into()),
             extension: extension,
             cache_enabled: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
             cache: BTreeMap::new().into(),
         }
     }
@@ -99,21 +171,48 @@ impl ZipModuleResolver {
     #[inline(always)]
     #[must_use]
     pub fn loaded(&self) -> bool {
-        return self.zip.borrow().is_some();
+        return self.archive.borrow().is_some();
     }
 
+    /// The format of the loaded archive.
+    #[inline(always)]
+    #[must_use]
+    pub fn archive_format(&self) -> Option<&'static str> {
+        self.archive.borrow().as_ref().map(|a| a.format())
+    }
+
+    /// Load an archive, the format is detected from its magic bytes.
     #[inline]
     pub fn load(&mut self, reader: Cursor<Vec<u8>>) -> ResolverResult<()> {
-        match ZipArchive::new(reader) {
-            Ok(z) => {
-                self.zip = RefCell::new(Some(z));
+        let bytes = reader.into_inner();
+        self.bundle_hash = Some(bundle_hash(&bytes));
 
-                Ok(())
-            }
-            Err(err) => {
-                Err(ResolverError::InvalidZip(err))
+        let archive = open_archive(bytes)?;
+        self.archive = RefCell::new(Some(archive));
+
+        #[cfg(feature = "baked_consts")]
+        {
+            self.baked_consts = None;
+
+            let manifest_path = PathBuf::from(BAKED_CONSTS_MANIFEST);
+            if self.has_file(manifest_path.clone()) {
+                let manifest = self.get_file(manifest_path)?;
+                self.baked_consts = Some(Rc::new(load_manifest(&manifest)?));
             }
         }
+
+        Ok(())
+    }
+
+    /// Were the consts of the loaded archive baked offline (see `bake_consts`)?
+    #[inline(always)]
+    #[must_use]
+    pub fn has_baked_consts(&self) -> bool {
+        #[cfg(feature = "baked_consts")]
+        return self.baked_consts.is_some();
+
+        #[cfg(not(feature = "baked_consts"))]
+        return false;
     }
 
     #[inline(always)]
@@ -133,11 +232,11 @@ impl ZipModuleResolver {
     pub fn init_with_scope(&mut self, engine: &Engine, scope: Scope<'static>) -> ResolverResult<Option<AST>> {
         self.set_scope(scope);
 
-        #[cfg(feature = "json_config")]
+        #[cfg(feature = "config")]
         self.load_config(engine)?;
 
         cfg_if! {
-            if #[cfg(feature = "json_config")] {
+            if #[cfg(feature = "config")] {
                 self.compile_entrypoints(engine)
             } else {
                 Ok(None)
@@ -162,11 +261,28 @@ impl ZipModuleResolver {
     /// Get the scope.
     #[inline(always)]
     #[must_use]
-    #[cfg(feature = "json_config")]
+    #[cfg(feature = "config")]
     pub fn config(&self) -> Config {
         self.config.as_ref().unwrap().clone()
     }
 
+    /// Get the entrypoint group compiled in addition to the global entrypoints
+    /// (`None` compiles every group).
+    #[inline(always)]
+    #[must_use]
+    #[cfg(feature = "config")]
+    pub fn entrypoint_group(&self) -> Option<&str> {
+        self.entrypoint_group.as_ref().map(String::as_str)
+    }
+
+    /// Set the entrypoint group (i.e. the endpoint being invoked).
+    #[inline(always)]
+    #[cfg(feature = "config")]
+    pub fn set_entrypoint_group(&mut self, group: Option<String>) -> &mut Self {
+        self.entrypoint_group = group;
+        self
+    }
+
     /// Get the base path for script files.
     #[inline(always)]
     #[must_use]
@@ -202,6 +318,51 @@ impl ZipModuleResolver {
         self
     }
 
+    /// Get the largest (uncompressed) file size the resolver will read.
+    #[inline(always)]
+    #[must_use]
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    /// Set the largest (uncompressed) file size the resolver will read.
+    #[inline(always)]
+    pub fn set_max_file_size(&mut self, max_file_size: u64) -> &mut Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// The SHA-256 hash of the loaded bundle.
+    #[inline(always)]
+    #[must_use]
+    pub fn bundle_hash(&self) -> Option<&BundleHash> {
+        self.bundle_hash.as_ref()
+    }
+
+    /// Get the module cache shared with other resolvers.
+    #[inline(always)]
+    #[must_use]
+    pub fn shared_cache(&self) -> Option<&SharedModuleCache> {
+        self.shared_cache.as_ref()
+    }
+
+    /// Share a module cache with other resolvers (modules are keyed by bundle hash, so
+    /// resolvers for different bundles may share one cache).
+    #[inline(always)]
+    pub fn set_shared_cache(&mut self, cache: Option<SharedModuleCache>) -> &mut Self {
+        self.shared_cache = cache;
+        self
+    }
+
+    /// Identifies the scope modules are compiled with (i.e. the serialized `ENV`), modules
+    /// are only shared with resolvers using the same key. Leave empty only if the scope is
+    /// the same for every resolver sharing the cache.
+    #[inline(always)]
+    pub fn set_scope_key(&mut self, key: Vec<u8>) -> &mut Self {
+        self.scope_key = key;
+        self
+    }
+
     /// Is the cache enabled?
     #[inline(always)]
     #[must_use]
@@ -217,7 +378,10 @@ impl ZipModuleResolver {
             return false;
         }
 
-        let file_path = self.get_file_path(path.as_ref(), source_path, None);
+        let file_path = match self.get_file_path(path.as_ref(), source_path, None) {
+            Ok(file_path) => file_path,
+            Err(_) => return false,
+        };
 
         let cache = locked_read(&self.cache);
 
@@ -244,26 +408,75 @@ impl ZipModuleResolver {
     ) -> Option<Shared<Module>> {
         let file_path = self.get_file_path(path.as_ref(),
                                            source_path.as_ref().map(<_>::as_ref),
-                                           None);
+                                           None).ok()?;
         locked_write(&self.cache)
             .remove_entry(&file_path)
             .map(|(.., v)| v)
     }
 
-    #[inline(always)]
-    #[cfg(feature = "json_config")]
-    pub fn load_config_source(&mut self) -> ResolverResult<String> {
+    /// The config file extensions supported by the enabled features.
+    #[cfg(feature = "config")]
+    pub fn config_extensions() -> Vec<&'static str> {
+        #[allow(unused_mut)]
+        let mut extensions = Vec::new();
+
+        #[cfg(feature = "json_config")]
+        extensions.push(JSON_EXTENSION);
+        #[cfg(feature = "toml_config")]
+        extensions.push(TOML_EXTENSION);
+        #[cfg(feature = "yaml_config")]
+        extensions.push(YAML_EXTENSION);
+
+        extensions
+    }
+
+    /// Find the extension of the config file in the archive (only one format may be present).
+    #[cfg(feature = "config")]
+    pub fn config_extension(&self) -> ResolverResult<&'static str> {
         if !self.loaded() {
             return Err(ResolverError::NotReady);
         }
 
+        let present = Self::config_extensions().into_iter()
+            .filter(|ext| {
+                self.get_file_path(CFG_FILE, None, Some(ext.to_string()))
+                    .map_or(false, |path| self.has_file(path))
+            })
+            .collect::<Vec<_>>();
+
+        return match present.len() {
+            0 => Err(ResolverError::FileNotFound),
+            1 => Ok(present[0]),
+            _ => Err(ResolverError::MultipleConfigs(present.iter()
+                .map(|ext| format!("{CFG_FILE}.{ext}"))
+                .collect())),
+        };
+    }
+
+    #[inline(always)]
+    #[cfg(feature = "config")]
+    pub fn load_config_source(&mut self) -> ResolverResult<String> {
+        let extension = self.config_extension()?;
+
         self.get_file(self.get_file_path(CFG_FILE, None,
-                                         Some(JSON_EXTENSION.to_string())))
+                                         Some(extension.to_string()))?)
     }
 
-    #[cfg(feature = "json_config")]
+    #[cfg(feature = "config")]
     pub fn load_config(&mut self, engine: &Engine) -> ResolverResult<()> {
-        let cfg_map = self.load_json_with_engine(CFG_FILE, engine)?;
+        let extension = self.config_extension()?;
+
+        let cfg_map = match extension {
+            #[cfg(feature = "json_config")]
+            JSON_EXTENSION => self.load_json_with_engine(CFG_FILE, engine)?,
+            #[cfg(feature = "toml_config")]
+            TOML_EXTENSION => toml_to_map(&self.load_config_source()?)?,
+            #[cfg(feature = "yaml_config")]
+            YAML_EXTENSION => yaml_to_map(&self.load_config_source()?)?,
+            _ => return Err(ResolverError::FileNotFound),
+        };
+        #[cfg(not(feature = "json_config"))]
+        let _ = engine;
 
         self.config = Some(Config::new(cfg_map));
 
@@ -280,7 +493,7 @@ impl ZipModuleResolver {
     pub fn load_json_with_engine(&mut self, path: &str, engine: &Engine) -> ResolverResult<Map> {
         let json_source = self.get_file(
             self.get_file_path(path, None,
-                               Some(JSON_EXTENSION.to_string())))?;
+                               Some(JSON_EXTENSION.to_string()))?)?;
         let json_map = engine.parse_json(json_source, true).map_err(|err| {
             ResolverError::JsonParseFailed(err.to_string())
         })?;
@@ -288,61 +501,105 @@ impl ZipModuleResolver {
         Ok(json_map)
     }
 
-    #[must_use]
+    /// Resolve `path` to a normalized archive path. Relative paths are relative to the base
+    /// path (or the importing source), absolute paths are relative to the archive root.
     pub fn get_file_path(&self, path: &str, source_path: Option<&str>,
-                         custom_extension: Option<String>) -> PathBuf {
-        let path = Path::new(path);
-
-        let mut file_path;
-
-        if path.is_relative() {
-            file_path = self
-                .base_path
-                .clone()
-                .or_else(|| source_path.map(|p| p.into()))
-                .unwrap_or_default();
-            file_path.push(path);
-        } else {
-            file_path = path.into();
+                         custom_extension: Option<String>) -> ResolverResult<PathBuf> {
+        let path = path.replace('\\', "/");
+
+        let mut file_path = PathBuf::new();
+        if !path.starts_with('/') {
+            if let Some(base) = self.base_path.clone()
+                .or_else(|| source_path.map(|p| p.into())) {
+                file_path = base;
+            }
         }
+        file_path.push(path);
+
+        let mut file_path = normalize_path(&file_path)?;
 
         if custom_extension.is_some() {
             file_path.set_extension(custom_extension.unwrap());
         } else {
             file_path.set_extension(self.extension.as_str());
         }
-        file_path
+
+        Ok(file_path)
     }
 
     #[inline(always)]
-    pub fn get_source_path(&self, path: &str, source_path: Option<&str>) -> PathBuf {
+    pub fn get_source_path(&self, path: &str, source_path: Option<&str>) -> ResolverResult<PathBuf> {
         self.get_file_path(path, source_path,
                            Some(RHAI_EXTENSION.to_string()))
     }
 
     #[inline]
     pub fn get_file(&self, file_path: PathBuf) -> ResolverResult<String> {
+        let bytes = self.get_file_bytes(file_path)?;
+
+        String::from_utf8(bytes).map_err(|err| {
+            ResolverError::FileReadFailed(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+        })
+    }
+
+    #[inline(always)]
+    pub fn get_file_bytes(&self, file_path: PathBuf) -> ResolverResult<Vec<u8>> {
+        self.get_file_bytes_with_limit(file_path, self.max_file_size)
+    }
+
+    pub fn get_file_bytes_with_limit(&self, file_path: PathBuf, limit: u64) -> ResolverResult<Vec<u8>> {
         if !self.loaded() {
             return Err(ResolverError::NotReady);
         }
 
-        let mut zip_rc = RefCell::borrow_mut(&self.zip);
-        let zip = zip_rc.as_mut().unwrap();
-        let mut file = zip.by_name(file_path.to_str().unwrap())
-            .map_err(|_err| {
-                ResolverError::FileNotFound
-            })?;
+        let name = path_to_name(&normalize_path(&file_path)?)?;
+        let limit = limit.min(self.max_file_size);
 
-        let mut string = String::new();
+        let mut archive_rc = RefCell::borrow_mut(&self.archive);
+        let archive = archive_rc.as_mut().unwrap();
 
-        match file.read_to_string(&mut string) {
-            Ok(_) => {
-                Ok(string)
-            }
-            Err(err) => {
-                Err(ResolverError::FileReadFailed(err))
-            }
+        archive.read_file(&name, limit)
+    }
+
+    /// Read an asset, `path` is relative to the assets directory declared in the config
+    /// and may not resolve outside of it.
+    #[cfg(feature = "config")]
+    pub fn get_asset(&self, path: &str) -> ResolverResult<Vec<u8>> {
+        let config = self.config.as_ref().ok_or(ResolverError::NotReady)?;
+
+        let dir = match config.get_str(CFG_KEY_ASSETS_DIR) {
+            Some(dir) => normalize_path(Path::new(&dir))?,
+            None => return Err(ResolverError::AssetsNotDeclared),
+        };
+        let file_path = normalize_path(&dir.join(path.replace('\\', "/").trim_start_matches('/')))?;
+        if !file_path.starts_with(&dir) || file_path == dir {
+            return Err(ResolverError::AssetOutsideDir(path.to_string()));
         }
+
+        let limit = config.get_int(CFG_KEY_ASSETS_MAX_SIZE)
+            .filter(|max| *max > 0)
+            .map_or(self.max_file_size, |max| max as u64);
+
+        self.get_file_bytes_with_limit(file_path, limit)
+    }
+
+    /// Does the file exist in the archive?
+    #[inline]
+    #[must_use]
+    pub fn has_file(&self, file_path: PathBuf) -> bool {
+        if !self.loaded() {
+            return false;
+        }
+
+        let name = match normalize_path(&file_path).and_then(|p| path_to_name(&p)) {
+            Ok(name) => name,
+            Err(_) => return false,
+        };
+
+        let mut archive_rc = RefCell::borrow_mut(&self.archive);
+        let archive = archive_rc.as_mut().unwrap();
+
+        archive.has_file(&name)
     }
 
     #[inline(always)]
@@ -352,76 +609,400 @@ impl ZipModuleResolver {
         self.compile_with_scope(&mut scope, engine, source)
     }
 
+    /// Compile the script at `file_path`, scripts with baked consts have them pushed
+    /// into the scope instead of being split out and evaluated.
+    pub fn compile_file_with_scope(&self, scope: &mut Scope, engine: &Engine,
+                                   file_path: &Path, source: String) -> ResolverResult<Option<AST>> {
+        self.eval_file_consts(scope, engine, file_path, &source)?;
+
+        self.compile_file_body(scope, engine, &source)
+    }
+
+    /// Push the consts of the script at `file_path` into the scope, returning the names of
+    /// those exported (`export const`).
+    fn eval_file_consts(&self, scope: &mut Scope, engine: &Engine, file_path: &Path,
+                        source: &str) -> ResolverResult<Vec<String>> {
+        #[cfg(feature = "baked_consts")]
+        if let Some(baked) = self.baked_consts.as_ref() {
+            let mut exported = Vec::new();
+            if let Some(consts) = baked.get(file_path) {
+                for (name, value, is_exported) in consts {
+                    scope.push_constant_dynamic(name.clone(), value.clone());
+                    if *is_exported {
+                        exported.push(name.clone());
+                    }
+                }
+            }
+
+            return Ok(exported);
+        }
+
+        #[cfg(not(feature = "baked_consts"))]
+        let _ = file_path;
+
+        eval_consts(scope, engine, source)
+    }
+
+    /// Compile a script without its consts (see `eval_file_consts`).
+    fn compile_file_body(&self, scope: &Scope, engine: &Engine,
+                         source: &str) -> ResolverResult<Option<AST>> {
+        #[cfg(feature = "baked_consts")]
+        if self.baked_consts.is_some() {
+            return Ok(Some(engine.compile_with_scope(scope, source).map_err(|err| {
+                ResolverError::ParseError(err)
+            })?));
+        }
+
+        compile_body(scope, engine, source)
+    }
+
     pub fn compile_with_scope(&self, scope: &mut Scope, engine: &Engine,
                               source: String) -> ResolverResult<Option<AST>> {
-        return match split_source_const(&source) {
-            None => {
-                Ok(Some(engine.compile_with_scope(scope, &source).map_err(|err| {
-                    ResolverError::ParseError(err)
-                })?))
-            }
-            Some((preamble, consts, body)) => {
-                if !consts.is_empty() {
-                    // Load const into scope and discard AST.
-                    engine.eval_with_scope(scope, &consts).map_err(|err| {
-                        ResolverError::EvalError(*err)
-                    })?;
-                }
+        eval_consts(scope, engine, &source)?;
 
-                if !body.is_empty() || !preamble.is_empty() {
-                    // Compile main body as AST.
-                    return Ok(Some(engine.compile_scripts_with_scope(scope, [&preamble, &body]).map_err(|err| {
-                        ResolverError::ParseError(err)
-                    })?));
-                }
+        compile_body(scope, engine, &source)
+    }
 
-                Ok(None)
+    /// How positions in the compiled script at `file_path` map back to the file.
+    pub fn source_map(&self, file_path: &Path) -> ResolverResult<SourceMap> {
+        // Scripts with baked consts keep their positions (see `bake_consts`).
+        #[cfg(feature = "baked_consts")]
+        if self.baked_consts.is_some() {
+            return Ok(SourceMap::identity());
+        }
+
+        let source = self.get_file(file_path.to_path_buf())?;
+
+        Ok(source_map_for(&source))
+    }
+
+    /// Remap the positions of a runtime error raised by the script at `file_path` (and of
+    /// the errors it wraps from function calls) to the original files.
+    pub fn remap_eval_error(&self, file_path: &Path, err: EvalAltResult) -> EvalAltResult {
+        let map = self.source_map(file_path).unwrap_or_default();
+
+        self.remap_eval_error_with(&map, file_path, err)
+    }
+
+    /// Remap the positions of a runtime error raised calling an entrypoint function (i.e.
+    /// by `call_fn`) to the entrypoint files defining the functions involved.
+    #[cfg(feature = "config")]
+    pub fn remap_entrypoint_error(&self, err: EvalAltResult) -> EvalAltResult {
+        self.remap_eval_error_with(&SourceMap::identity(), Path::new(""), err)
+    }
+
+    fn remap_eval_error_with(&self, map: &SourceMap, file_path: &Path,
+                             err: EvalAltResult) -> EvalAltResult {
+        return match err {
+            EvalAltResult::ErrorInFunctionCall(name, mut src, inner, pos) => {
+                let inner = if !src.is_empty() && Path::new(&src) != file_path {
+                    self.remap_eval_error(Path::new(&src), *inner)
+                } else if let Some(fn_path) = self.entrypoint_fn_path(&name, &src) {
+                    // Report the entrypoint file the function is defined in.
+                    src = fn_path.to_string_lossy().to_string();
+                    self.remap_eval_error(fn_path, *inner)
+                } else {
+                    self.remap_eval_error_with(map, file_path, *inner)
+                };
+
+                EvalAltResult::ErrorInFunctionCall(name, src, Box::new(inner), map.locate(pos))
             }
+            // Errors within an imported module were remapped when it was loaded.
+            err => map.remap_eval_error(err),
         };
     }
 
+    /// The entrypoint file defining `name`, entrypoints are compiled without a source so
+    /// only functions called from a sourceless script can be entrypoint functions.
+    #[inline(always)]
+    fn entrypoint_fn_path(&self, name: &str, src: &str) -> Option<&Path> {
+        #[cfg(feature = "config")]
+        if src.is_empty() {
+            return self.entrypoint_fns.get(name).map(|p| p.as_path());
+        }
+
+        let _ = (name, src);
+        None
+    }
+
     pub fn compile_path_with_scope(&self, path: String, scope: &mut Scope,
                                    engine: &Engine) -> ResolverResult<Option<AST>> {
-        let source_path = self.get_source_path(path.as_str(), None);
+        let source_path = self.get_source_path(path.as_str(), None)?;
 
         let source = self.get_file(source_path.clone())?;
 
-        self.compile_with_scope(scope, engine, source)
+        self.compile_file_with_scope(scope, engine, &source_path, source)
             .map_err(|err| {
-                ResolverError::SourceCompileFailed(source_path.to_str().unwrap().to_string(),
+                ResolverError::SourceCompileFailed(source_path.to_string_lossy().to_string(),
                                                    Box::new(err))
             })
     }
 
-    #[cfg(feature = "json_config")]
+    #[cfg(feature = "config")]
     pub fn compile_entrypoints(&mut self, engine: &Engine) -> ResolverResult<Option<AST>> {
         if !self.loaded() {
             return Err(ResolverError::NotReady);
         }
 
-        let mut scope = self.scope.to_owned();
-
-        return match self.config.as_ref().unwrap().get_str_array(CFG_KEY_GLOBAL_ENTRYPOINTS) {
-            Some(entrypoints) => {
-                let mut ast: Option<AST> = None;
-                for name in entrypoints {
-                    let cur_ast = self.compile_path_with_scope(name, &mut scope,
-                                                               engine)?;
-                    if cur_ast.is_some() {
-                        if ast.is_some() {
-                            ast = Some(ast.unwrap().merge(&cur_ast.unwrap()));
-                        } else {
-                            ast = Some(cur_ast.unwrap());
-                        }
-                    }
+        let entrypoints = self.entrypoint_paths()?;
+        if entrypoints.is_empty() {
+            return Ok(None);
+        }
+
+        let base = self.scope.to_owned();
+        let config_globals = self.config_globals(&base)?;
+        let mut globals = config_globals.clone();
+
+        // Evaluate the consts of each entrypoint in isolation (seeing only the config
+        // globals), collecting those exported as globals.
+        let mut files: Vec<(PathBuf, String, Scope)> = Vec::new();
+        for name in entrypoints {
+            let source_path = self.get_source_path(name.as_str(), None)?;
+            let source = self.get_file(source_path.clone())?;
+            let failed = |err: ResolverError| {
+                ResolverError::SourceCompileFailed(source_path.to_string_lossy().to_string(),
+                                                   Box::new(err))
+            };
+
+            let mut scope = base.clone();
+            scope.extend(config_globals.iter());
+            let start = scope.len();
+
+            let exported = self.eval_file_consts(&mut scope, engine, &source_path, &source)
+                .map_err(failed)?;
+
+            let consts: Scope = scope.iter().skip(start).collect();
+            for (name, _, value) in consts.iter() {
+                if exported.iter().any(|e| e == name) {
+                    add_global(&base, &mut globals, name, value).map_err(failed)?;
                 }
+            }
 
-                self.set_scope(scope);
+            files.push((source_path, source, consts));
+        }
 
-                Ok(ast)
+        // Compile each entrypoint seeing every global and only its own consts.
+        let mut ast: Option<AST> = None;
+        self.entrypoint_fns.clear();
+        for (source_path, source, consts) in files {
+            let mut scope = base.clone();
+            scope.extend(globals.iter());
+            scope.extend(consts.iter());
+
+            let cur_ast = self.compile_file_body(&scope, engine, &source)
+                .map_err(|err| {
+                    ResolverError::SourceCompileFailed(source_path.to_string_lossy().to_string(),
+                                                       Box::new(err))
+                })?;
+            if let Some(cur_ast) = cur_ast.as_ref() {
+                for f in cur_ast.iter_functions() {
+                    self.entrypoint_fns.entry(f.name.to_string())
+                        .or_insert_with(|| source_path.clone());
+                }
             }
-            None => Ok(None)
+            if cur_ast.is_some() {
+                if ast.is_some() {
+                    ast = Some(ast.unwrap().merge(&cur_ast.unwrap()));
+                } else {
+                    ast = Some(cur_ast.unwrap());
+                }
+            }
+        }
+
+        // Modules (and the entrypoints at runtime) see the globals, not entrypoint consts.
+        let mut scope = base;
+        scope.extend(globals.iter());
+        self.set_scope(scope);
+
+        Ok(ast)
+    }
+
+    /// The globals declared in the config (`global.constants`), checked against `base`.
+    #[cfg(feature = "config")]
+    fn config_globals(&self, base: &Scope) -> ResolverResult<Scope<'static>> {
+        let config = self.config.as_ref().ok_or(ResolverError::NotReady)?;
+
+        let mut globals = Scope::new();
+        let consts = match config.try_get(CFG_KEY_GLOBAL_CONSTANTS) {
+            Ok(consts) => consts,
+            Err(ConfigError::Missing(_)) => return Ok(globals),
+            Err(err) => return Err(ResolverError::Config(err)),
         };
+
+        let consts = consts.read_lock::<Map>().ok_or_else(|| {
+            ResolverError::Config(ConfigError::TypeMismatch {
+                path: CFG_KEY_GLOBAL_CONSTANTS.to_string(),
+                expected: "map".to_string(),
+                found: consts.type_name().to_string(),
+            })
+        })?;
+        for (name, value) in consts.iter() {
+            add_global(base, &mut globals, name, value.clone())?;
+        }
+
+        Ok(globals)
+    }
+
+    /// The entrypoints to compile: the global group, then the selected group (or every
+    /// group if none is selected), without duplicates.
+    #[cfg(feature = "config")]
+    #[inline(always)]
+    pub fn entrypoint_paths(&self) -> ResolverResult<Vec<String>> {
+        self.entrypoint_paths_for(self.entrypoint_group())
+    }
+
+    #[cfg(feature = "config")]
+    pub fn entrypoint_paths_for(&self, group: Option<&str>) -> ResolverResult<Vec<String>> {
+        let config = self.config.as_ref().ok_or(ResolverError::NotReady)?;
+
+        let mut keys = vec![CFG_KEY_GLOBAL_ENTRYPOINTS.to_string()];
+        match group {
+            Some(group) => keys.push(format!("{CFG_KEY_ENTRYPOINT_GROUPS}.{group}")),
+            None => {
+                let groups = config.get(CFG_KEY_ENTRYPOINT_GROUPS);
+                if let Some(groups) = groups.read_lock::<Map>() {
+                    for group in groups.keys() {
+                        keys.push(format!("{CFG_KEY_ENTRYPOINT_GROUPS}.{group}"));
+                    }
+                }
+            }
+        }
+
+        let mut paths: Vec<String> = Vec::new();
+        for key in keys {
+            let group_paths = match config.try_get_str_array(&key) {
+                Ok(group_paths) => group_paths,
+                Err(ConfigError::Missing(_)) => continue,
+                Err(err) => return Err(ResolverError::Config(err)),
+            };
+
+            for path in group_paths {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// The names of all files in the archive.
+    pub fn file_names(&self) -> Vec<String> {
+        if !self.loaded() {
+            return Vec::new();
+        }
+
+        let archive_rc = RefCell::borrow(&self.archive);
+        archive_rc.as_ref().unwrap().file_names()
+    }
+
+    /// Build the import graph of every entrypoint group.
+    #[cfg(feature = "config")]
+    pub fn analyze_imports(&self, engine: &Engine) -> ResolverResult<ImportGraph> {
+        let roots = self.entrypoint_paths_for(None)?.iter()
+            .map(|path| self.get_source_path(path, None))
+            .collect::<ResolverResult<Vec<_>>>()?;
+
+        self.analyze_imports_from(engine, roots)
+    }
+
+    /// Build the import graph by parsing each file reachable from `roots` (without
+    /// evaluating any module or const) and collecting its literal `import` paths.
+    pub fn analyze_imports_from(&self, engine: &Engine,
+                                roots: Vec<PathBuf>) -> ResolverResult<ImportGraph> {
+        let mut graph = ImportGraph::default();
+        graph.roots = roots.clone();
+
+        // false = being visited, true = done.
+        let mut visited: BTreeMap<PathBuf, bool> = BTreeMap::new();
+        let mut stack: Vec<PathBuf> = Vec::new();
+
+        for root in roots {
+            self.visit_imports(engine, root, &mut graph, &mut visited, &mut stack)?;
+        }
+
+        for name in self.file_names() {
+            let path = PathBuf::from(name);
+            let is_script = path.extension()
+                .map_or(false, |ext| ext == self.extension.as_str());
+            if is_script && !graph.edges.contains_key(&path) {
+                graph.unreachable.push(path);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    fn visit_imports(
+        &self,
+        engine: &Engine,
+        file_path: PathBuf,
+        graph: &mut ImportGraph,
+        visited: &mut BTreeMap<PathBuf, bool>,
+        stack: &mut Vec<PathBuf>,
+    ) -> ResolverResult<()> {
+        match visited.get(&file_path) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                let start = stack.iter().position(|f| *f == file_path).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(file_path);
+                graph.cycles.push(cycle);
+
+                return Ok(());
+            }
+            None => {}
+        }
+
+        visited.insert(file_path.clone(), false);
+        stack.push(file_path.clone());
+
+        // Parse the whole file as is, its consts are not evaluated.
+        let source = self.get_file(file_path.clone())?;
+        let ast = engine.compile_with_scope(&self.scope, &source)
+            .map_err(|err| {
+                ResolverError::SourceCompileFailed(file_path.to_string_lossy().to_string(),
+                                                   Box::new(ResolverError::ParseError(err)))
+            })?;
+
+        let source_dir = file_path.parent().map(|p| p.to_string_lossy().to_string());
+
+        let mut imports: Vec<PathBuf> = Vec::new();
+        for import in collect_imports(&ast) {
+            let import = match import {
+                Some(import) => import,
+                None => {
+                    if !graph.dynamic.contains(&file_path) {
+                        graph.dynamic.push(file_path.clone());
+                    }
+                    continue;
+                }
+            };
+
+            let import_path = match self.get_file_path(&import, source_dir.as_deref(), None) {
+                Ok(import_path) => import_path,
+                Err(_) => {
+                    graph.missing.push((file_path.clone(), import));
+                    continue;
+                }
+            };
+            if !self.has_file(import_path.clone()) {
+                graph.missing.push((file_path.clone(), import));
+                continue;
+            }
+
+            if !imports.contains(&import_path) {
+                imports.push(import_path.clone());
+            }
+            self.visit_imports(engine, import_path, graph, visited, stack)?;
+        }
+
+        graph.edges.insert(file_path.clone(), imports);
+        stack.pop();
+        visited.insert(file_path.clone(), true);
+        graph.order.push(file_path);
+
+        Ok(())
     }
 
     /// Resolve a module based on a path.
@@ -441,7 +1022,11 @@ impl ZipModuleResolver {
             .and_then(|p| Path::new(p).parent().map(|p| p.to_string_lossy()));
 
         let file_path = self.get_file_path(path, source_path.as_ref()
-            .map(|p| p.as_ref()), None);
+            .map(|p| p.as_ref()), None)
+            .map_err(|err| {
+                EvalAltResult::ErrorInModule(path.to_string(),
+                                             Box::new(map_resolver_err_to_eval_err(err)), pos)
+            })?;
 
         if self.is_cache_enabled() {
             #[cfg(not(feature = "sync"))]
@@ -454,42 +1039,72 @@ impl ZipModuleResolver {
             }
         }
 
-        let script = self.get_file(file_path.clone())
-            .map_err(|_err| {
-                Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))
-            })?;
-
-        // Clone to avoid importing any module consts.
-        let mut scope = self.scope.clone();
+        self.load_module(engine, global.is_some(), file_path, path, pos)
+    }
 
-        let ast = self.compile_with_scope(&mut scope, engine, script)
-            .map_err(|err| {
-                EvalAltResult::ErrorInModule(path.to_string(),
-                                             Box::new(map_resolver_err_to_eval_err(err)), pos)
-            })?;
-        if ast.is_none() {
-            return Err(Box::new(
-                EvalAltResult::ErrorInModule(path.to_string(),
-                                             Box::new(map_resolver_err_to_eval_err(
-                                                 ResolverError::NoAstProduced)), pos)));
-        }
-        let mut ast = ast.unwrap();
+    /// Evaluate the module at `file_path` (caching it if enabled), compiling it unless
+    /// the shared cache has it.
+    fn load_module(
+        &self,
+        engine: &Engine,
+        _has_global: bool,
+        file_path: PathBuf,
+        path: &str,
+        pos: Position,
+    ) -> Result<Rc<Module>, Box<EvalAltResult>> {
+        let shared = match (self.shared_cache.as_ref(), self.bundle_hash) {
+            (Some(cache), Some(hash)) => Some((cache, hash)),
+            _ => None,
+        };
 
-        ast.set_source(path);
+        // The globals depend on the entrypoint group compiled.
+        #[cfg(feature = "config")]
+        let group = self.entrypoint_group();
+        #[cfg(not(feature = "config"))]
+        let group: Option<&str> = None;
+
+        let cached = shared.and_then(|(cache, hash)| {
+            cache.get(&hash, &self.scope_key, group, &file_path)
+        });
+        let compiled = match cached {
+            Some(compiled) => compiled,
+            None => {
+                let compiled = Rc::new(self.compile_module(engine, &file_path, path, pos)?);
+                if let Some((cache, hash)) = shared {
+                    cache.insert(hash, self.scope_key.clone(), group.map(str::to_string),
+                                 file_path.clone(), compiled.clone());
+                }
+                compiled
+            }
+        };
 
-        let scope = Scope::new();
+        // The module body sees the globals and its own consts (only `exported` are exported).
+        let mut scope = self.scope.clone();
+        for (name, value) in compiled.consts.iter() {
+            scope.push_constant_dynamic(name.clone(), value.clone());
+        }
+        let module_scope = scope.clone();
 
-        let m: Shared<Module> = if let Some(_global) = global {
-            Module::eval_ast_as_new(scope, &ast, engine)
+        let mut m: Module = if _has_global {
+            Module::eval_ast_as_new(module_scope, &compiled.ast, engine)
             // TODO: this needs to be made public.
-            //Module::eval_ast_as_new_raw(engine, scope, global, &ast)
+            //Module::eval_ast_as_new_raw(engine, module_scope, global, &ast)
         } else {
-            Module::eval_ast_as_new(scope, &ast, engine)
+            Module::eval_ast_as_new(module_scope, &compiled.ast, engine)
         }
             .map_err(|err| Box::new(
-                EvalAltResult::ErrorInModule(path.to_string(), err, pos)
-            ))?
-            .into();
+                EvalAltResult::ErrorInModule(path.to_string(),
+                                             Box::new(self.remap_eval_error(&file_path, *err)), pos)
+            ))?;
+
+        // The consts were evaluated separately, so export them as rhai would have.
+        for name in compiled.exported.iter() {
+            if let Some(value) = scope.get_value::<Dynamic>(name) {
+                m.set_var(name.clone(), value);
+            }
+        }
+
+        let m: Shared<Module> = m.into();
 
         if self.is_cache_enabled() {
             locked_write(&self.cache).insert(file_path, m.clone());
@@ -497,6 +1112,50 @@ impl ZipModuleResolver {
 
         Ok(m)
     }
+
+    /// Evaluate the consts of the module at `file_path` and compile its body.
+    fn compile_module(
+        &self,
+        engine: &Engine,
+        file_path: &Path,
+        path: &str,
+        pos: Position,
+    ) -> Result<CompiledModule, Box<EvalAltResult>> {
+        let script = self.get_file(file_path.to_path_buf())
+            .map_err(|_err| {
+                Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))
+            })?;
+
+        // Clone to avoid importing any module consts (modules only see the globals).
+        let mut scope = self.scope.clone();
+        let start = scope.len();
+
+        let compiled = self.eval_file_consts(&mut scope, engine, file_path, &script)
+            .and_then(|exported| {
+                Ok((exported, self.compile_file_body(&scope, engine, &script)?))
+            });
+        let (exported, ast) = compiled
+            .map_err(|err| {
+                let err = ResolverError::SourceCompileFailed(file_path.to_string_lossy().to_string(),
+                                                             Box::new(err));
+                EvalAltResult::ErrorInModule(path.to_string(),
+                                             Box::new(map_resolver_err_to_eval_err(err)), pos)
+            })?;
+        let mut ast = ast.ok_or_else(|| Box::new(
+            EvalAltResult::ErrorInModule(path.to_string(),
+                                         Box::new(map_resolver_err_to_eval_err(
+                                             ResolverError::NoAstProduced)), pos)))?;
+
+        // Use the archive path so nested imports resolve relative to this module, whichever
+        // module imported it.
+        ast.set_source(file_path.to_string_lossy().as_ref());
+
+        let consts = scope.iter().skip(start)
+            .map(|(name, _, value)| (name.to_string(), value))
+            .collect();
+
+        Ok(CompiledModule { consts, exported, ast })
+    }
 }
 
 // Implement the 'ModuleResolver' trait.
@@ -533,12 +1192,13 @@ impl ModuleResolver for ZipModuleResolver {
         pos: Position,
     ) -> Option<Result<AST, Box<EvalAltResult>>> {
         // Construct the script file path
-        let file_path = self.get_file_path(path, source_path, None);
-
-        let script = self.get_file(file_path.clone())
-            .map_err(|_err| {
-                Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))
-            }).ok().unwrap();
+        let script = match self.get_file_path(path, source_path, None)
+            .and_then(|file_path| self.get_file(file_path)) {
+            Ok(script) => script,
+            Err(_err) => {
+                return Some(Err(Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))));
+            }
+        };
 
         // Load the script file and compile it
         Some(
@@ -591,11 +1251,46 @@ pub fn locked_write<T>(value: &Locked<T>) -> LockGuardMut<T> {
     return value.write().unwrap();
 }
 
+// Paths
+/// Normalize an archive path: `\` separators become `/`, empty and `.` segments are
+/// dropped and `..` is resolved. Escaping above the archive root is an error.
+pub fn normalize_path(path: &Path) -> ResolverResult<PathBuf> {
+    let path_str = path.to_str()
+        .ok_or_else(|| ResolverError::InvalidPath(path.to_string_lossy().to_string()))?;
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path_str.split(|c| c == '/' || c == '\\') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(ResolverError::PathEscapesRoot(path_str.to_string()));
+                }
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(ResolverError::InvalidPath(path_str.to_string()));
+    }
+
+    Ok(PathBuf::from(segments.join("/")))
+}
+
+#[inline(always)]
+fn path_to_name(path: &Path) -> ResolverResult<String> {
+    return match path.to_str() {
+        Some(name) => Ok(name.to_string()),
+        None => Err(ResolverError::InvalidPath(path.to_string_lossy().to_string())),
+    };
+}
+
 // Source
-fn split_source_const(source: &str) -> Option<(&str, &str, &str)> {
+pub(crate) fn split_source_const(source: &str) -> Option<(&str, &str, &str)> {
     return match split_source(source, "fn ") {
         None => {
-            match split_source(source, "const ") {
+            match split_source_consts(source) {
                 None => None,
                 Some((before_const, consts)) => {
                     Some((before_const, consts, ""))
@@ -603,7 +1298,7 @@ fn split_source_const(source: &str) -> Option<(&str, &str, &str)> {
             }
         }
         Some((preamble, body)) => {
-            match split_source(&preamble, "const ") {
+            match split_source_consts(&preamble) {
                 None => {
                     Some((preamble, "", body))
                 }
@@ -615,6 +1310,94 @@ fn split_source_const(source: &str) -> Option<(&str, &str, &str)> {
     };
 }
 
+/// Add a global constant, it must not already be a global (or in the base scope).
+#[cfg(feature = "config")]
+fn add_global(base: &Scope, globals: &mut Scope<'static>, name: &str,
+              value: Dynamic) -> ResolverResult<()> {
+    if base.contains(name) || globals.contains(name) {
+        return Err(ResolverError::DuplicateGlobal(name.to_string()));
+    }
+
+    globals.push_constant_dynamic(name.to_string(), value);
+
+    Ok(())
+}
+
+/// Split at the first const, keeping an `export` before it with the consts.
+fn split_source_consts(source: &str) -> Option<(&str, &str)> {
+    let n = source.find("const ")?;
+
+    let before = source[..n].trim_end();
+    let n = match before.strip_suffix("export") {
+        Some(rest) if !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_') => rest.len(),
+        _ => n,
+    };
+
+    return match source.split_at(n) {
+        (a, b) => Some((a.trim(), b.trim()))
+    };
+}
+
+/// Evaluate the consts of a script into the scope, returning the names of those exported.
+pub(crate) fn eval_consts(scope: &mut Scope, engine: &Engine,
+                          source: &str) -> ResolverResult<Vec<String>> {
+    let consts = match split_source_const(source) {
+        Some((_, consts, _)) if !consts.is_empty() => consts,
+        _ => return Ok(Vec::new()),
+    };
+
+    let map = SourceMap::from_fragments(source, &[consts]);
+    let ast = engine.compile_with_scope(scope, consts).map_err(|err| {
+        ResolverError::ParseError(map.remap_parse_error(err))
+    })?;
+
+    let exported = ast.statements().iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Var(x, options, ..)
+            if options.contains(ASTFlags::CONSTANT) && options.contains(ASTFlags::EXPORTED) => {
+                Some(x.0.name.to_string())
+            }
+            _ => None,
+        })
+        .collect();
+
+    // Load const into scope and discard AST.
+    engine.run_ast_with_scope(scope, &ast).map_err(|err| {
+        ResolverError::EvalError(map.remap_eval_error(*err))
+    })?;
+
+    Ok(exported)
+}
+
+/// Compile a script, less its consts, as AST.
+fn compile_body(scope: &Scope, engine: &Engine, source: &str) -> ResolverResult<Option<AST>> {
+    return match split_source_const(source) {
+        None => {
+            Ok(Some(engine.compile_with_scope(scope, source).map_err(|err| {
+                ResolverError::ParseError(err)
+            })?))
+        }
+        Some((preamble, _, body)) => {
+            if body.is_empty() && preamble.is_empty() {
+                return Ok(None);
+            }
+
+            let map = SourceMap::from_fragments(source, &[preamble, body]);
+            Ok(Some(engine.compile_scripts_with_scope(scope, [preamble, body]).map_err(|err| {
+                ResolverError::ParseError(map.remap_parse_error(err))
+            })?))
+        }
+    };
+}
+
+/// The source map for the fragments `compile_body` compiles.
+fn source_map_for(source: &str) -> SourceMap {
+    return match split_source_const(source) {
+        None => SourceMap::identity(),
+        Some((preamble, _, body)) => SourceMap::from_fragments(source, &[preamble, body]),
+    };
+}
+
 #[inline(always)]
 fn split_source<'a>(source: &'a str, pat: &str) -> Option<(&'a str, &'a str)> {
     return match source.find(pat) {
@@ -626,3 +1409,370 @@ fn split_source<'a>(source: &'a str, pat: &str) -> Option<(&'a str, &'a str)> {
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use rhai::INT;
+    use zip::write::FileOptions;
+
+    use crate::cache::ModuleCacheStats;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    fn bundle(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buf);
+            for (name, content) in files {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        buf.into_inner()
+    }
+
+    fn init_resolver(engine: &Engine, files: &[(&str, &str)]) -> ZipModuleResolver {
+        init_resolver_for(engine, files, None)
+    }
+
+    fn init_resolver_for(engine: &Engine, files: &[(&str, &str)], group: Option<&str>) -> ZipModuleResolver {
+        let mut resolver = ZipModuleResolver::new();
+        resolver.load_from_bytes(bundle(files)).unwrap();
+        resolver.set_entrypoint_group(group.map(str::to_string));
+
+        let mut scope = Scope::new();
+        scope.push_constant("ENV", 42 as INT);
+        resolver.init_with_scope(engine, scope).unwrap();
+
+        resolver
+    }
+
+    const CONFIG: &'static str = r#"{"global":{"entrypoints":["main"],"constants":{"GREETING":"hi"}}}"#;
+
+    #[test]
+    fn imported_module_exports_consts() {
+        let mut engine = Engine::new();
+        let resolver = init_resolver(&engine, &[
+            ("config.json", CONFIG),
+            ("main.rhai", "fn main() {}"),
+            ("lib.rhai", "export let ENV_SEEN = ENV;\nexport const EXPORTED = GREETING + \"!\";\nconst PRIVATE = 1;\nfn get() { 1 }"),
+        ]);
+        let mut scope = resolver.scope().clone();
+        engine.set_module_resolver(resolver);
+
+        let exported: String = engine.eval_with_scope(&mut scope, r#"import "lib" as m; m::EXPORTED"#)
+            .unwrap();
+        assert_eq!(exported, "hi!");
+
+        // The module body sees the globals (and ENV) at runtime.
+        let env: INT = engine.eval_with_scope(&mut scope, r#"import "lib" as m; m::ENV_SEEN"#)
+            .unwrap();
+        assert_eq!(env, 42);
+
+        assert!(engine.eval_with_scope::<INT>(&mut scope, r#"import "lib" as m; m::PRIVATE"#).is_err());
+    }
+
+    #[test]
+    fn shared_cache_reuses_compiled_modules_but_evaluates_fresh() {
+        let counter = Rc::new(RefCell::new(0 as INT));
+        let files = [
+            ("config.json", CONFIG),
+            ("main.rhai", "fn main() {}"),
+            ("lib.rhai", "export let VALUE = next();\nexport const EXPORTED = GREETING;\nfn get() { 1 }"),
+        ];
+        let cache = SharedModuleCache::new();
+
+        let import = |scope_key: &[u8]| -> INT {
+            let mut engine = Engine::new();
+            let counter = counter.clone();
+            engine.register_fn("next", move || -> INT {
+                *counter.borrow_mut() += 1;
+                *counter.borrow()
+            });
+
+            let mut resolver = init_resolver(&engine, &files);
+            resolver.set_shared_cache(Some(cache.clone()));
+            resolver.set_scope_key(scope_key.to_vec());
+            let mut scope = resolver.scope().clone();
+            engine.set_module_resolver(resolver);
+
+            engine.eval_with_scope(&mut scope, r#"import "lib" as m; m::VALUE"#).unwrap()
+        };
+
+        assert_eq!(import(b"env"), 1);
+        assert_eq!(import(b"env"), 2);
+        assert_eq!(cache.stats(), ModuleCacheStats { hits: 1, misses: 1 });
+
+        // A different scope is compiled separately.
+        assert_eq!(import(b"other env"), 3);
+        assert_eq!(cache.stats(), ModuleCacheStats { hits: 1, misses: 2 });
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn shared_cache_keeps_modules_of_each_group_apart() {
+        let files = [
+            ("config.json", r#"{"global":{"entrypoints":["main"]},"entrypoints":{"handle":["handle"],"query":["query"]}}"#),
+            ("main.rhai", "fn main() {}"),
+            ("handle.rhai", "export const MODE = \"handle\";\nfn handle() {}"),
+            ("query.rhai", "export const MODE = \"query\";\nfn query() {}"),
+            ("lib.rhai", "export const SEEN = MODE;\nfn get() { 1 }"),
+        ];
+        let cache = SharedModuleCache::new();
+
+        let import = |group: &str| -> String {
+            let mut engine = Engine::new();
+            let mut resolver = init_resolver_for(&engine, &files, Some(group));
+            resolver.set_shared_cache(Some(cache.clone()));
+            let mut scope = resolver.scope().clone();
+            engine.set_module_resolver(resolver);
+
+            engine.eval_with_scope(&mut scope, r#"import "lib" as m; m::SEEN"#).unwrap()
+        };
+
+        // A module compiled for one group is not reused with another group's globals.
+        assert_eq!(import("handle"), "handle");
+        assert_eq!(import("query"), "query");
+        assert_eq!(import("handle"), "handle");
+        assert_eq!(cache.stats(), ModuleCacheStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn normalize_path_resolves_segments() {
+        let normalize = |path: &str| normalize_path(Path::new(path)).unwrap();
+
+        assert_eq!(normalize("a/./b/../c"), PathBuf::from("a/c"));
+        assert_eq!(normalize("a\\b\\..\\c"), PathBuf::from("a/c"));
+        assert_eq!(normalize("/a//b/"), PathBuf::from("a/b"));
+
+        assert!(matches!(normalize_path(Path::new("../a")), Err(ResolverError::PathEscapesRoot(_))));
+        assert!(matches!(normalize_path(Path::new("a/../../b")), Err(ResolverError::PathEscapesRoot(_))));
+        assert!(matches!(normalize_path(Path::new("a\\..\\..\\b")), Err(ResolverError::PathEscapesRoot(_))));
+        assert!(matches!(normalize_path(Path::new("")), Err(ResolverError::InvalidPath(_))));
+        assert!(matches!(normalize_path(Path::new("a/..")), Err(ResolverError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn get_file_path_resolves_imports() {
+        let resolver = ZipModuleResolver::new();
+        let resolve = |path: &str, source: Option<&str>| resolver.get_file_path(path, source, None);
+
+        assert_eq!(resolve("lib", None).unwrap(), PathBuf::from("lib.rhai"));
+        assert_eq!(resolve("lib", Some("dir")).unwrap(), PathBuf::from("dir/lib.rhai"));
+        assert_eq!(resolve("../lib", Some("dir/sub")).unwrap(), PathBuf::from("dir/lib.rhai"));
+        assert_eq!(resolve("sub\\lib", Some("dir")).unwrap(), PathBuf::from("dir/sub/lib.rhai"));
+
+        // Absolute imports are relative to the archive root, not the importing source.
+        assert_eq!(resolve("/lib", Some("dir")).unwrap(), PathBuf::from("lib.rhai"));
+        assert_eq!(resolve("\\lib", Some("dir")).unwrap(), PathBuf::from("lib.rhai"));
+
+        assert!(matches!(resolve("../lib", Some("dir")), Err(ResolverError::PathEscapesRoot(_))));
+        assert!(matches!(resolve("/../lib", Some("dir")), Err(ResolverError::PathEscapesRoot(_))));
+        assert!(matches!(resolve("..\\..\\lib", Some("dir")), Err(ResolverError::PathEscapesRoot(_))));
+
+        let mut based = ZipModuleResolver::new();
+        based.set_base_path("base");
+        assert_eq!(based.get_file_path("lib", Some("dir"), None).unwrap(), PathBuf::from("base/lib.rhai"));
+        assert_eq!(based.get_file_path("/lib", Some("dir"), None).unwrap(), PathBuf::from("lib.rhai"));
+    }
+
+    #[test]
+    fn get_asset_stays_within_assets_dir() {
+        let engine = Engine::new();
+        let resolver = init_resolver(&engine, &[
+            ("config.json", r#"{"global":{"entrypoints":["main"]},"assets":{"dir":"assets"}}"#),
+            ("main.rhai", "fn main() {}"),
+            ("assets/logo.txt", "logo"),
+            ("assets_other/logo.txt", "other"),
+            ("secret.txt", "secret"),
+        ]);
+
+        assert_eq!(resolver.get_asset("logo.txt").unwrap(), b"logo".to_vec());
+        assert_eq!(resolver.get_asset("/logo.txt").unwrap(), b"logo".to_vec());
+        assert_eq!(resolver.get_asset("sub\\..\\logo.txt").unwrap(), b"logo".to_vec());
+
+        for path in &["../secret.txt", "..\\secret.txt", "/../secret.txt", "../assets_other/logo.txt", "", "."] {
+            assert!(matches!(resolver.get_asset(path), Err(ResolverError::AssetOutsideDir(_))),
+                    "'{path}' was not rejected");
+        }
+        assert!(matches!(resolver.get_asset("../../secret.txt"), Err(ResolverError::PathEscapesRoot(_))));
+
+        let undeclared = init_resolver(&engine, &[
+            ("config.json", CONFIG),
+            ("main.rhai", "fn main() {}"),
+            ("assets/logo.txt", "logo"),
+        ]);
+        assert!(matches!(undeclared.get_asset("logo.txt"), Err(ResolverError::AssetsNotDeclared)));
+    }
+
+    #[test]
+    fn analyze_imports_reports_missing_cycles_unreachable_and_dynamic() {
+        let engine = Engine::new();
+        let resolver = init_resolver(&engine, &[
+            ("config.json", CONFIG),
+            ("main.rhai", "import \"a\" as a;\nimport \"missing\" as m;\nlet name = \"b\";\nimport name as n;\nfn main() {}"),
+            // Parsed only, evaluating the const would fail.
+            ("a.rhai", "import \"b\" as b;\nexport const A = not_a_function();"),
+            ("b.rhai", "import \"a\" as a;"),
+            ("c.rhai", "export const C = 1;"),
+        ]);
+
+        let graph = resolver.analyze_imports(&engine).unwrap();
+        assert!(!graph.is_valid());
+        assert_eq!(graph.roots, vec![PathBuf::from("main.rhai")]);
+        assert_eq!(graph.missing, vec![(PathBuf::from("main.rhai"), "missing".to_string())]);
+        assert_eq!(graph.cycles, vec![vec![PathBuf::from("a.rhai"), PathBuf::from("b.rhai"),
+                                           PathBuf::from("a.rhai")]]);
+        assert_eq!(graph.unreachable, vec![PathBuf::from("c.rhai")]);
+        assert_eq!(graph.dynamic, vec![PathBuf::from("main.rhai")]);
+        assert_eq!(graph.errors(), vec![
+            "'main.rhai' imports missing module 'missing'".to_string(),
+            "import cycle: a.rhai -> b.rhai -> a.rhai".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn analyze_imports_orders_dependencies_first() {
+        let engine = Engine::new();
+        let resolver = init_resolver(&engine, &[
+            ("config.json", CONFIG),
+            ("main.rhai", "import \"x\" as x;\nimport \"lib/y\" as y;\nfn main() {}"),
+            ("x.rhai", "import \"lib/z\" as z;"),
+            ("lib/y.rhai", "import \"z\" as z;"),
+            ("lib/z.rhai", "export const Z = 1;"),
+        ]);
+
+        let graph = resolver.analyze_imports(&engine).unwrap();
+        assert!(graph.is_valid());
+        assert!(graph.errors().is_empty());
+        assert!(graph.unreachable.is_empty());
+        assert!(graph.dynamic.is_empty());
+        assert_eq!(graph.order, vec![PathBuf::from("lib/z.rhai"), PathBuf::from("x.rhai"),
+                                     PathBuf::from("lib/y.rhai"), PathBuf::from("main.rhai")]);
+        assert_eq!(graph.edges[&PathBuf::from("main.rhai")],
+                   vec![PathBuf::from("x.rhai"), PathBuf::from("lib/y.rhai")]);
+        assert_eq!(graph.edges[&PathBuf::from("lib/z.rhai")], Vec::<PathBuf>::new());
+    }
+
+    const GROUPS_CONFIG: &'static str = r#"{"global":{"entrypoints":["main"]},
+        "entrypoints":{"handle":["handle","shared"],"query":["query","shared"]}}"#;
+
+    fn groups_bundle() -> Vec<u8> {
+        bundle(&[
+            ("config.json", GROUPS_CONFIG),
+            ("main.rhai", "export const BASE = 1;\nfn main() { BASE }"),
+            ("handle.rhai", "fn handle() { BASE + 1 }"),
+            ("query.rhai", "fn query() { BASE + 2 }"),
+            ("shared.rhai", "fn shared() { BASE + 3 }"),
+        ])
+    }
+
+    fn compile_group(engine: &Engine, bytes: Vec<u8>, group: Option<&str>) -> ResolverResult<Option<AST>> {
+        let mut resolver = ZipModuleResolver::new();
+        resolver.load_from_bytes(bytes).unwrap();
+        resolver.set_entrypoint_group(group.map(str::to_string));
+
+        let mut scope = Scope::new();
+        scope.push_constant("ENV", 42 as INT);
+        resolver.init_with_scope(engine, scope)
+    }
+
+    fn fn_names(ast: &AST) -> Vec<String> {
+        let mut names: Vec<String> = ast.iter_functions().map(|f| f.name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn entrypoint_paths_for_includes_the_global_group() {
+        let engine = Engine::new();
+        let resolver = init_resolver(&engine, &[
+            ("config.json", GROUPS_CONFIG),
+            ("main.rhai", "fn main() {}"),
+            ("handle.rhai", "fn handle() {}"),
+            ("query.rhai", "fn query() {}"),
+            ("shared.rhai", "fn shared() {}"),
+        ]);
+
+        assert_eq!(resolver.entrypoint_paths_for(Some("handle")).unwrap(), vec!["main", "handle", "shared"]);
+        assert_eq!(resolver.entrypoint_paths_for(Some("query")).unwrap(), vec!["main", "query", "shared"]);
+        assert_eq!(resolver.entrypoint_paths_for(Some("missing")).unwrap(), vec!["main"]);
+        assert_eq!(resolver.entrypoint_paths_for(None).unwrap(), vec!["main", "handle", "shared", "query"]);
+    }
+
+    #[test]
+    fn compile_entrypoints_compiles_only_the_selected_group() {
+        let engine = Engine::new();
+
+        let ast = compile_group(&engine, groups_bundle(), Some("handle")).unwrap().unwrap();
+        assert_eq!(fn_names(&ast), vec!["handle", "main", "shared"]);
+
+        let ast = compile_group(&engine, groups_bundle(), Some("query")).unwrap().unwrap();
+        assert_eq!(fn_names(&ast), vec!["main", "query", "shared"]);
+
+        let ast = compile_group(&engine, groups_bundle(), None).unwrap().unwrap();
+        assert_eq!(fn_names(&ast), vec!["handle", "main", "query", "shared"]);
+    }
+
+    #[test]
+    fn globals_declared_twice_are_rejected() {
+        let engine = Engine::new();
+        let duplicate_global = |res: ResolverResult<Option<AST>>| -> String {
+            return match res {
+                Err(ResolverError::DuplicateGlobal(name)) => name,
+                Err(ResolverError::SourceCompileFailed(_, err)) => match *err {
+                    ResolverError::DuplicateGlobal(name) => name,
+                    err => panic!("unexpected error: {err}"),
+                },
+                Err(err) => panic!("unexpected error: {err}"),
+                Ok(_) => panic!("expected a duplicate global"),
+            };
+        };
+
+        // Exported by two entrypoints.
+        let bytes = bundle(&[
+            ("config.json", r#"{"global":{"entrypoints":["main","other"]}}"#),
+            ("main.rhai", "export const RATE = 1;\nfn main() {}"),
+            ("other.rhai", "export const RATE = 2;\nfn other() {}"),
+        ]);
+        assert_eq!(duplicate_global(compile_group(&engine, bytes, None)), "RATE");
+
+        // Exported by entrypoints of different groups compiled together.
+        let bytes = bundle(&[
+            ("config.json", r#"{"global":{"entrypoints":["main"]},"entrypoints":{"a":["a"],"b":["b"]}}"#),
+            ("main.rhai", "fn main() {}"),
+            ("a.rhai", "export const RATE = 1;\nfn a() {}"),
+            ("b.rhai", "export const RATE = 2;\nfn b() {}"),
+        ]);
+        assert_eq!(duplicate_global(compile_group(&engine, bytes.clone(), None)), "RATE");
+        compile_group(&engine, bytes.clone(), Some("a")).unwrap();
+        compile_group(&engine, bytes, Some("b")).unwrap();
+
+        // Declared in the config and exported by an entrypoint.
+        let bytes = bundle(&[
+            ("config.json", CONFIG),
+            ("main.rhai", "export const GREETING = \"hello\";\nfn main() {}"),
+        ]);
+        assert_eq!(duplicate_global(compile_group(&engine, bytes, None)), "GREETING");
+
+        // Already in the base scope.
+        let bytes = bundle(&[
+            ("config.json", r#"{"global":{"entrypoints":["main"],"constants":{"ENV":1}}}"#),
+            ("main.rhai", "fn main() {}"),
+        ]);
+        assert_eq!(duplicate_global(compile_group(&engine, bytes, None)), "ENV");
+
+        // Consts which aren't exported stay private to their entrypoint.
+        let bytes = bundle(&[
+            ("config.json", r#"{"global":{"entrypoints":["main","other"]}}"#),
+            ("main.rhai", "const RATE = 1;\nfn main() {}"),
+            ("other.rhai", "const RATE = 2;\nfn other() {}"),
+        ]);
+        compile_group(&engine, bytes, None).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 use std::{fmt};
 use std::error::Error;
-use rhai::{EvalAltResult, ParseError};
+use rhai::{EvalAltResult, ParseError, Position};
+#[cfg(feature = "zip_archive")]
 use zip::result::ZipError;
 
 pub type ResolverResult<T> = Result<T, ResolverError>;
@@ -8,11 +9,33 @@ pub type ResolverResult<T> = Result<T, ResolverError>;
 #[derive(Debug)]
 pub enum ResolverError {
     /// This file is probably not a zip archive
+    #[cfg(feature = "zip_archive")]
     InvalidZip(ZipError),
 
+    /// The archive is malformed
+    InvalidArchive(String),
+
+    /// The archive format was not recognised (or its feature is not enabled)
+    UnknownArchiveFormat,
+
     /// The requested file could not be found in the archive
     FileNotFound,
 
+    /// The file exceeds the size limit: (file, limit)
+    FileTooLarge(String, u64),
+
+    /// No assets directory is declared in the config
+    AssetsNotDeclared,
+
+    /// The asset path resolves outside of the assets directory
+    AssetOutsideDir(String),
+
+    /// The path is not valid UTF-8 (or is empty)
+    InvalidPath(String),
+
+    /// The path resolves above the archive root
+    PathEscapesRoot(String),
+
     /// The requested file could not be read from the archive
     FileReadFailed(std::io::Error),
 
@@ -22,7 +45,19 @@ pub enum ResolverError {
     /// The requested json file could not be parsed
     JsonParseFailed(String),
 
-    /// The requested file could not be read from the archive
+    /// The config file could not be parsed (or converted to a map)
+    ConfigParseFailed(String),
+
+    /// The config is invalid
+    Config(ConfigError),
+
+    /// More than one config file format is present in the archive
+    MultipleConfigs(Vec<String>),
+
+    /// A global constant is declared more than once
+    DuplicateGlobal(String),
+
+    /// The file failed to compile: (file, error)
     SourceCompileFailed(String, Box<ResolverError>),
 
     /// Wrapped parse error
@@ -39,22 +74,93 @@ pub enum ResolverError {
 impl fmt::Display for ResolverError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            #[cfg(feature = "zip_archive")]
             ResolverError::InvalidZip(err) => write!(fmt, "invalid resolver Zip: {}", err),
+            ResolverError::InvalidArchive(err) => write!(fmt, "invalid resolver archive: {}", err),
+            ResolverError::UnknownArchiveFormat => write!(fmt, "unknown resolver archive format"),
             ResolverError::FileNotFound => write!(fmt, "specified file not found in resolver archive"),
+            ResolverError::FileTooLarge(file, limit) => write!(fmt, "file '{}' exceeds the size limit of {} bytes", file, limit),
+            ResolverError::AssetsNotDeclared => write!(fmt, "no assets directory is declared in config"),
+            ResolverError::AssetOutsideDir(path) => write!(fmt, "asset '{}' is outside of the assets directory", path),
+            ResolverError::InvalidPath(path) => write!(fmt, "invalid path '{}'", path),
+            ResolverError::PathEscapesRoot(path) => write!(fmt, "path '{}' escapes the archive root", path),
             ResolverError::FileReadFailed(err) => write!(fmt, "file read failed: {}", err),
             ResolverError::NoAstProduced => write!(fmt, "no AST produced (is the file empty?)"),
             ResolverError::JsonParseFailed(err) => write!(fmt, "json file parse failed: {}", err),
+            ResolverError::ConfigParseFailed(err) => write!(fmt, "config file parse failed: {}", err),
+            ResolverError::Config(err) => write!(fmt, "{}", err),
+            ResolverError::MultipleConfigs(files) => write!(fmt, "multiple config files found, only one is permitted: {}", files.join(", ")),
+            ResolverError::DuplicateGlobal(name) => write!(fmt, "global constant '{}' is declared more than once", name),
             ResolverError::SourceCompileFailed(s, err) if s.is_empty() => write!(fmt, "compile failed: {}", err),
-            ResolverError::SourceCompileFailed(s, err) => write!(fmt, "compile of '{}' failed: {}", s, err),
+            ResolverError::SourceCompileFailed(s, err) => match self.location() {
+                Some(location) => write!(fmt, "compile of '{}' failed: {}", location, err),
+                None => write!(fmt, "compile of '{}' failed: {}", s, err),
+            },
             ResolverError::ParseError( err) => write!(fmt, "parse error: {}", err),
             ResolverError::EvalError( err) => write!(fmt, "eval error: {}", err),
-            ResolverError::NotReady => write!(fmt, "the resolver zip isn't ready, did you load?"),
+            ResolverError::NotReady => write!(fmt, "the resolver archive isn't ready, did you load?"),
         }
     }
 }
 
 impl Error for ResolverError {}
 
+impl ResolverError {
+    /// The position of the wrapped parse or eval error (`NONE` under `no_position`).
+    pub fn position(&self) -> Position {
+        return match self {
+            ResolverError::SourceCompileFailed(_, err) => err.position(),
+            ResolverError::ParseError(err) => err.1,
+            ResolverError::EvalError(err) => err.position(),
+            _ => Position::NONE,
+        };
+    }
+
+    /// Where in the original script a compile failed, as `file:line:col`.
+    pub fn location(&self) -> Option<String> {
+        return match self {
+            ResolverError::SourceCompileFailed(file, err) if !file.is_empty() => {
+                let pos = err.position();
+                pos.line().map(|line| format!("{}:{}:{}", file, line, pos.position().unwrap_or(0)))
+            }
+            _ => None,
+        };
+    }
+}
+
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// No value exists at the path
+    Missing(String),
+
+    /// The value at the path is not of the expected type
+    TypeMismatch { path: String, expected: String, found: String },
+
+    /// The path could not be parsed
+    InvalidPath { path: String, reason: String },
+
+    /// An element of the array at the path is not of the expected type
+    BadElement { path: String, index: usize, expected: String, found: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Missing(path) => write!(fmt, "config key '{}' is missing", path),
+            ConfigError::TypeMismatch { path, expected, found } =>
+                write!(fmt, "config key '{}' must be {} (found {})", path, expected, found),
+            ConfigError::InvalidPath { path, reason } =>
+                write!(fmt, "invalid config path '{}': {}", path, reason),
+            ConfigError::BadElement { path, index, expected, found } =>
+                write!(fmt, "config key '{}[{}]' must be {} (found {})", path, index, expected, found),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
 pub fn map_resolver_err_to_eval_err(err: ResolverError) -> EvalAltResult {
     return EvalAltResult::ErrorSystem("".to_string(),
                                       Box::new(err));
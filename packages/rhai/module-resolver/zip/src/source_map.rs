@@ -0,0 +1,156 @@
+use rhai::{EvalAltResult, ParseError, Position};
+
+/// Maps positions in the text rhai compiled (fragments of a script concatenated in order)
+/// back to the original script.
+///
+/// Each fragment is a contiguous slice of the original, so only the position of its first
+/// character needs recording: later lines of a fragment keep their column and are offset
+/// by a fixed number of lines.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// (compiled line, compiled column, original line, original column) of each fragment.
+    segments: Vec<(usize, usize, usize, usize)>,
+}
+
+impl SourceMap {
+    /// The compiled text is the original script.
+    #[inline(always)]
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// Map `fragments` (slices of `source`) which were compiled one after another.
+    pub fn from_fragments(source: &str, fragments: &[&str]) -> Self {
+        let mut segments = Vec::with_capacity(fragments.len());
+        let (mut line, mut col) = (1, 1);
+
+        for fragment in fragments {
+            let (orig_line, orig_col) = advance((1, 1), &source[..offset_in(source, fragment)]);
+            segments.push((line, col, orig_line, orig_col));
+
+            let next = advance((line, col), fragment);
+            line = next.0;
+            col = next.1;
+        }
+
+        Self { segments }
+    }
+
+    /// The original position of `pos` (unchanged if there is no position).
+    pub fn locate(&self, pos: Position) -> Position {
+        let line = match pos.line() {
+            Some(line) => line,
+            None => return pos,
+        };
+        let col = pos.position().unwrap_or(0);
+
+        let segment = self.segments.iter()
+            .rev()
+            .find(|(gen_line, gen_col, ..)| (*gen_line, *gen_col) <= (line, col));
+
+        return match segment {
+            None => pos,
+            Some((gen_line, gen_col, orig_line, orig_col)) => {
+                let (line, col) = if line == *gen_line {
+                    (*orig_line, orig_col + (col - gen_col))
+                } else {
+                    (orig_line + (line - gen_line), col)
+                };
+
+                Position::new(clamp(line).max(1), clamp(col))
+            }
+        };
+    }
+
+    pub fn remap_parse_error(&self, err: ParseError) -> ParseError {
+        ParseError(err.0, self.locate(err.1))
+    }
+
+    /// Remap the position of a runtime error (not of any error it wraps).
+    pub fn remap_eval_error(&self, mut err: EvalAltResult) -> EvalAltResult {
+        let pos = self.locate(err.position());
+        err.set_position(pos);
+
+        err
+    }
+}
+
+/// The byte offset of `fragment` within `source` (which it must be a slice of).
+#[inline(always)]
+fn offset_in(source: &str, fragment: &str) -> usize {
+    fragment.as_ptr() as usize - source.as_ptr() as usize
+}
+
+/// The position following `text` when it starts at `pos`.
+fn advance(pos: (usize, usize), text: &str) -> (usize, usize) {
+    return match text.rfind('\n') {
+        None => (pos.0, pos.1 + text.chars().count()),
+        Some(n) => (pos.0 + text.matches('\n').count(), 1 + text[n + 1..].chars().count()),
+    };
+}
+
+#[inline(always)]
+fn clamp(n: usize) -> u16 {
+    n.min(u16::MAX as usize) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use rhai::Engine;
+
+    use super::*;
+
+    const SOURCE: &'static str = "let a = 1;\nconst X = 2;\nfn f() {\n  let = 1;\n}";
+
+    fn fragments() -> (&'static str, &'static str) {
+        let body = &SOURCE[SOURCE.find("fn").unwrap()..];
+
+        (&SOURCE[..10], body)
+    }
+
+    #[test]
+    fn identity_keeps_positions() {
+        let map = SourceMap::identity();
+
+        assert_eq!(map.locate(Position::new(3, 7)), Position::new(3, 7));
+        assert_eq!(map.locate(Position::NONE), Position::NONE);
+    }
+
+    #[test]
+    fn locate_maps_fragments_to_the_original() {
+        let (preamble, body) = fragments();
+        let map = SourceMap::from_fragments(SOURCE, &[preamble, body]);
+
+        // The preamble starts the original.
+        assert_eq!(map.locate(Position::new(1, 5)), Position::new(1, 5));
+        // The body follows the preamble on the same compiled line.
+        assert_eq!(map.locate(Position::new(1, 11)), Position::new(3, 1));
+        assert_eq!(map.locate(Position::new(1, 14)), Position::new(3, 4));
+        // Later lines of a fragment keep their column.
+        assert_eq!(map.locate(Position::new(2, 3)), Position::new(4, 3));
+        assert_eq!(map.locate(Position::NONE), Position::NONE);
+    }
+
+    #[test]
+    fn remaps_parse_errors_of_compiled_fragments() {
+        let (preamble, body) = fragments();
+        let map = SourceMap::from_fragments(SOURCE, &[preamble, body]);
+
+        let err = Engine::new().compile_scripts_with_scope(&rhai::Scope::new(), [preamble, body])
+            .unwrap_err();
+        assert_eq!(err.1.line(), Some(2));
+
+        let err = map.remap_parse_error(err);
+        assert_eq!(err.1.line(), Some(4));
+    }
+
+    #[test]
+    fn remaps_eval_errors() {
+        let source = "const X = 1;\nfn f() {\n  throw 1;\n}";
+        let body = &source[source.find("fn").unwrap()..];
+        let map = SourceMap::from_fragments(source, &[body]);
+
+        let err = EvalAltResult::ErrorRuntime(1.into(), Position::new(2, 3));
+        assert_eq!(map.remap_eval_error(err).position(), Position::new(3, 3));
+    }
+}